@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+/// Probability model used by an agent to decide whether it picks up the item
+/// under it or drops the one it carries, given the local density `f`.
+pub trait DecisionModel: Send + Sync {
+    fn take_probability(&self, density: f64, k1: f64) -> f64;
+    fn drop_probability(&self, density: f64, k2: f64) -> f64;
+}
+
+/// Deneubourg / Lumer–Faieta model: `(k1 / (k1 + f))²` and `(f / (k2 + f))²`.
+pub struct Deneubourg;
+
+/// Sigmoid model: `1 - s(f)` to pick up and `s(f)` to drop, with
+/// `s(f) = (1 - e^(-slope·f)) / (1 + e^(-slope·f))`. Ignores `k1` and `k2`.
+pub struct Sigmoid {
+    pub slope: f64,
+}
+
+/// ATTA-style threshold model: always picks up below `k1` and always drops
+/// above `k2`, decaying as `(k1 / f)²` and `(f / k2)⁴` otherwise.
+pub struct Threshold;
+
+impl DecisionModel for Deneubourg {
+    fn take_probability(&self, density: f64, k1: f64) -> f64 {
+        let coeff = k1 / (k1 + density);
+        coeff * coeff
+    }
+    fn drop_probability(&self, density: f64, k2: f64) -> f64 {
+        let coeff = density / (k2 + density);
+        coeff * coeff
+    }
+}
+
+impl Sigmoid {
+    fn sigmoid(&self, density: f64) -> f64 {
+        let exp = (-self.slope * density).exp();
+        (1.0 - exp) / (1.0 + exp)
+    }
+}
+
+impl DecisionModel for Sigmoid {
+    fn take_probability(&self, density: f64, _k1: f64) -> f64 {
+        1.0 - self.sigmoid(density)
+    }
+    fn drop_probability(&self, density: f64, _k2: f64) -> f64 {
+        self.sigmoid(density)
+    }
+}
+
+impl DecisionModel for Threshold {
+    fn take_probability(&self, density: f64, k1: f64) -> f64 {
+        if density <= k1 {
+            return 1.0;
+        }
+        let coeff = k1 / density;
+        coeff * coeff
+    }
+    fn drop_probability(&self, density: f64, k2: f64) -> f64 {
+        if density >= k2 {
+            return 1.0;
+        }
+        (density / k2).powi(4)
+    }
+}
+
+/// Selects the decision model of every agent in a simulation.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecisionModelKind {
+    #[default]
+    Deneubourg,
+    Sigmoid {
+        slope: f64,
+    },
+    Threshold,
+    /// Not serializable.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<dyn DecisionModel>),
+}
+
+impl DecisionModel for DecisionModelKind {
+    fn take_probability(&self, density: f64, k1: f64) -> f64 {
+        match self {
+            DecisionModelKind::Deneubourg => Deneubourg.take_probability(density, k1),
+            DecisionModelKind::Sigmoid { slope } => {
+                Sigmoid { slope: *slope }.take_probability(density, k1)
+            }
+            DecisionModelKind::Threshold => Threshold.take_probability(density, k1),
+            DecisionModelKind::Custom(model) => model.take_probability(density, k1),
+        }
+    }
+    fn drop_probability(&self, density: f64, k2: f64) -> f64 {
+        match self {
+            DecisionModelKind::Deneubourg => Deneubourg.drop_probability(density, k2),
+            DecisionModelKind::Sigmoid { slope } => {
+                Sigmoid { slope: *slope }.drop_probability(density, k2)
+            }
+            DecisionModelKind::Threshold => Threshold.drop_probability(density, k2),
+            DecisionModelKind::Custom(model) => model.drop_probability(density, k2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probabilities_in_range() {
+        let models = [
+            DecisionModelKind::Deneubourg,
+            DecisionModelKind::Sigmoid { slope: 10.0 },
+            DecisionModelKind::Threshold,
+        ];
+        for model in models.iter() {
+            for step in 0..=10 {
+                let density = step as f64 / 10.0;
+                let take = model.take_probability(density, 0.35);
+                let drop = model.drop_probability(density, 0.35);
+                assert!((0.0..=1.0).contains(&take));
+                assert!((0.0..=1.0).contains(&drop));
+            }
+        }
+        assert_eq!(Deneubourg.take_probability(0.0, 0.35), 1.0);
+        assert_eq!(Deneubourg.drop_probability(0.0, 0.35), 0.0);
+        assert_eq!(Threshold.drop_probability(0.5, 0.35), 1.0);
+    }
+}
@@ -0,0 +1,119 @@
+const SQUARE: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+const HEX_EVEN: [(isize, isize); 6] = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)];
+const HEX_ODD: [(isize, isize); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)];
+
+/// Shape of the map cells.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Grid {
+    /// Square cells with 8 neighbours.
+    #[default]
+    Square,
+    /// Hexagonal cells with 6 neighbours, stored row by row with the odd
+    /// rows shifted half a cell to the right.
+    Hex,
+}
+
+fn is_odd(row: isize) -> bool {
+    row.rem_euclid(2) == 1
+}
+
+impl Grid {
+    /// Row and column offsets of the neighbours of a cell in row `i`.
+    pub fn neighbors(&self, i: usize) -> &'static [(isize, isize)] {
+        match self {
+            Grid::Square => &SQUARE,
+            Grid::Hex if is_odd(i as isize) => &HEX_ODD,
+            Grid::Hex => &HEX_EVEN,
+        }
+    }
+
+    /// Number of steps between a cell in row `i` and the cell `di` rows and
+    /// `dj` columns away from it.
+    pub fn steps(&self, i: usize, di: isize, dj: isize) -> usize {
+        match self {
+            Grid::Square => di.unsigned_abs().max(dj.unsigned_abs()),
+            Grid::Hex => {
+                let shift = is_odd(i as isize + di) as isize - is_odd(i as isize) as isize;
+                let dq = dj - (di - shift) / 2;
+                (dq.abs() + di.abs() + (dq + di).abs()) as usize / 2
+            }
+        }
+    }
+
+    /// Column offset of the mirror image, across the column of a cell in row
+    /// `i`, of the cell `di` rows and `dj` columns away from it.
+    pub fn mirror_column(&self, i: usize, di: isize, dj: isize) -> isize {
+        match self {
+            Grid::Square => -dj,
+            Grid::Hex => {
+                let shift = is_odd(i as isize + di) as isize - is_odd(i as isize) as isize;
+                -dj - shift
+            }
+        }
+    }
+
+    /// Distance between the centres of a cell in row `i` and of the cell
+    /// `di` rows and `dj` columns away from it, with neighbouring centres one
+    /// unit apart.
+    pub fn center_distance(&self, i: usize, di: isize, dj: isize) -> f64 {
+        let (dx, dy) = match self {
+            Grid::Square => (dj as f64, di as f64),
+            Grid::Hex => {
+                let shift = is_odd(i as isize + di) as isize - is_odd(i as isize) as isize;
+                (
+                    dj as f64 + shift as f64 / 2.0,
+                    di as f64 * 3f64.sqrt() / 2.0,
+                )
+            }
+        };
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_neighbors_are_one_step_away() {
+        for i in 0..2 {
+            let neighbors = Grid::Hex.neighbors(i);
+            assert_eq!(neighbors.len(), 6);
+            for &(di, dj) in neighbors {
+                assert_eq!(Grid::Hex.steps(i, di, dj), 1);
+                assert!((Grid::Hex.center_distance(i, di, dj) - 1.0).abs() < 1e-12);
+            }
+        }
+        assert_eq!(Grid::Hex.steps(0, 0, 3), 3);
+        assert_eq!(Grid::Hex.steps(0, 2, 1), 2);
+        assert_eq!(Grid::Hex.steps(1, 2, -1), 2);
+        assert_eq!(Grid::Hex.steps(0, 2, -2), 3);
+        assert_eq!(Grid::Square.steps(0, 2, -2), 2);
+    }
+
+    #[test]
+    fn mirrored_neighbors_are_neighbors() {
+        for i in 0..2 {
+            let neighbors = Grid::Hex.neighbors(i);
+            for &(di, dj) in neighbors {
+                let mirrored = (di, Grid::Hex.mirror_column(i, di, dj));
+                assert!(neighbors.contains(&mirrored));
+                assert_eq!(Grid::Hex.mirror_column(i, di, mirrored.1), dj);
+            }
+        }
+        assert_eq!(Grid::Hex.mirror_column(0, -1, -1), 0);
+        assert_eq!(Grid::Hex.mirror_column(1, 1, 1), 0);
+        assert_eq!(Grid::Square.mirror_column(0, -1, -1), 1);
+    }
+}
@@ -1,10 +1,19 @@
-use object::Object;
+use object::{DistanceMetric, Object};
 use rand::Rng;
 use std::{collections::VecDeque, fmt::Display};
 
+mod decision;
+mod grid;
+mod topology;
+
+pub use decision::{DecisionModel, DecisionModelKind, Deneubourg, Sigmoid, Threshold};
+pub use grid::Grid;
+pub use topology::Topology;
+
 type Vision<T> = Vec<Vec<T>>;
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AgentStates {
     CARRYING,
     SEARCHING,
@@ -13,25 +22,83 @@ pub enum AgentStates {
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     pub i: usize,
     pub j: usize,
 }
 
-#[derive(Clone, Copy)]
+/// Local density and probability behind the last pick-up or drop attempt of
+/// an agent.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Decision {
+    pub density: f64,
+    pub probability: f64,
+}
+
+/// Invariant broken by an agent update, see [`Agent::check_update`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Invariant {
+    /// The agent is carrying or finishing with an empty backpack.
+    EmptyBackpack,
+    /// The agent is searching or done with an item in its backpack.
+    FullBackpack,
+    /// The agent picked up an item from an empty cell.
+    PickedFromEmptyCell,
+    /// The agent dropped an item on an occupied cell.
+    DroppedOnOccupiedCell,
+    /// The cell of the agent in the returned vision does not match the map
+    /// after the pick-up or drop, or the lack of one.
+    CellNotUpdated,
+    /// A state change that an update cannot make.
+    UnexpectedTransition {
+        from: AgentStates,
+        to: AgentStates,
+    },
+    /// The agent ended the update on the cell it started it on.
+    DidNotMove,
+    /// The agent moved to a position outside the map.
+    OutOfBounds(Point),
+}
+
+pub const DEFAULT_K1: f64 = 0.35;
+pub const DEFAULT_K2: f64 = 0.35;
+pub const DEFAULT_ALPHA: f64 = 6.0;
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AgentConfig {
     vision_radius: usize,
     map_height: usize,
     map_width: usize,
     queue_size: usize,
+    k1: f64,
+    k2: f64,
+    alpha: f64,
+    decision_model: DecisionModelKind,
+    distance_metric: DistanceMetric,
+    topology: Topology,
+    grid: Grid,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ConfigError {
+    InvalidK1(f64),
+    InvalidK2(f64),
+    InvalidAlpha(f64),
+    InvalidMinkowskiOrder(f64),
+    InvalidSigmoidSlope(f64),
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Agent<T> {
     pos: Point,
     state: AgentStates,
     pub backpack: T,
     history: VecDeque<Point>,
     config: AgentConfig,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    last_decision: Option<Decision>,
 }
 
 impl Display for AgentStates {
@@ -53,45 +120,271 @@ impl Display for AgentStates {
     }
 }
 
-impl<T: Object + Clone + Copy> Agent<T> {
-    pub fn update_agent(&mut self, mapa: &Vision<T>) -> Vision<T> {
+impl Display for Invariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Invariant::EmptyBackpack => write!(f, "carrying with an empty backpack"),
+            Invariant::FullBackpack => write!(f, "not carrying but the backpack is full"),
+            Invariant::PickedFromEmptyCell => write!(f, "picked up from an empty cell"),
+            Invariant::DroppedOnOccupiedCell => write!(f, "dropped on an occupied cell"),
+            Invariant::CellNotUpdated => {
+                write!(f, "own cell in the vision does not match the action")
+            }
+            Invariant::UnexpectedTransition { from, to } => {
+                write!(f, "unexpected transition from {} to {}", from, to)
+            }
+            Invariant::DidNotMove => write!(f, "did not move"),
+            Invariant::OutOfBounds(pos) => {
+                write!(f, "moved out of the map to ({}, {})", pos.i, pos.j)
+            }
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::InvalidK1(value) => {
+                write!(f, "k1 must be positive and finite, got {}", value)
+            }
+            ConfigError::InvalidK2(value) => {
+                write!(f, "k2 must be positive and finite, got {}", value)
+            }
+            ConfigError::InvalidAlpha(value) => {
+                write!(f, "alpha must be positive and finite, got {}", value)
+            }
+            ConfigError::InvalidMinkowskiOrder(value) => {
+                write!(
+                    f,
+                    "Minkowski order must be finite and at least 1, got {}",
+                    value
+                )
+            }
+            ConfigError::InvalidSigmoidSlope(value) => {
+                write!(
+                    f,
+                    "sigmoid slope must be positive and finite, got {}",
+                    value
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn is_valid_param(value: f64) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+impl AgentConfig {
+    /// Builds an agent configuration, rejecting pick-up (`k1`), drop (`k2`)
+    /// and dissimilarity scale (`alpha`) values that are not positive and finite.
+    pub fn new(
+        vision_radius: usize,
+        map_height: usize,
+        map_width: usize,
+        k1: f64,
+        k2: f64,
+        alpha: f64,
+    ) -> Result<Self, ConfigError> {
+        if !is_valid_param(k1) {
+            return Err(ConfigError::InvalidK1(k1));
+        }
+        if !is_valid_param(k2) {
+            return Err(ConfigError::InvalidK2(k2));
+        }
+        if !is_valid_param(alpha) {
+            return Err(ConfigError::InvalidAlpha(alpha));
+        }
+        Ok(AgentConfig {
+            vision_radius,
+            map_height,
+            map_width,
+            queue_size: 8,
+            k1,
+            k2,
+            alpha,
+            decision_model: DecisionModelKind::default(),
+            distance_metric: DistanceMetric::default(),
+            topology: Topology::default(),
+            grid: Grid::default(),
+        })
+    }
+
+    pub fn with_decision_model(
+        mut self,
+        decision_model: DecisionModelKind,
+    ) -> Result<Self, ConfigError> {
+        if let DecisionModelKind::Sigmoid { slope } = decision_model {
+            if !is_valid_param(slope) {
+                return Err(ConfigError::InvalidSigmoidSlope(slope));
+            }
+        }
+        self.decision_model = decision_model;
+        Ok(self)
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_grid(mut self, grid: Grid) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    pub fn with_distance_metric(
+        mut self,
+        distance_metric: DistanceMetric,
+    ) -> Result<Self, ConfigError> {
+        if let DistanceMetric::Minkowski(p) = distance_metric {
+            if !(p.is_finite() && p >= 1.0) {
+                return Err(ConfigError::InvalidMinkowskiOrder(p));
+            }
+        }
+        self.distance_metric = distance_metric;
+        Ok(self)
+    }
+}
+
+/// Cell at the centre of a square `vision` window, where the agent stands.
+pub fn vision_center<T>(vision: &[Vec<T>]) -> &T {
+    let center = vision.len() / 2;
+    &vision[center][center]
+}
+
+/// Square window of `radius` cells around `pos`, as an agent standing there
+/// sees it: cells beyond the edges of a bounded map, or more than `radius`
+/// steps away on a hexagonal grid, are left empty.
+pub fn vision_window<T: Object + Clone>(
+    mapa: &[Vec<T>],
+    pos: Point,
+    radius: usize,
+    topology: Topology,
+    grid: Grid,
+) -> Vec<Vec<T>> {
+    let height = mapa.len();
+    let width = mapa.first().map_or(0, |row| row.len());
+    let radius = radius as isize;
+    (-radius..=radius)
+        .map(|di| {
+            (-radius..=radius)
+                .map(|dj| {
+                    let cell = match (
+                        topology.neighbor(pos.i, di, height),
+                        topology.neighbor(pos.j, dj, width),
+                    ) {
+                        (Some(i), Some(j)) => &mapa[i][j],
+                        _ => return T::clone_empty(),
+                    };
+                    match grid.steps(pos.i, di, dj) as isize > radius {
+                        true => T::clone_empty(),
+                        false => cell.clone(),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Local density of `item` among the items of a square `vision` window, the
+/// centre cell excluded: the summed similarity `1 - d / alpha` over the
+/// square of the number of neighbours, or 0 when it is not positive.
+pub fn local_density<T: Object>(
+    item: &T,
+    vision: &[Vec<T>],
+    alpha: f64,
+    metric: &DistanceMetric,
+) -> f64 {
+    let center = vision.len() / 2;
+    let mut density = 0.0;
+    let mut area = 0.0;
+    for (i, row) in vision.iter().enumerate() {
+        for (j, cel) in row.iter().enumerate() {
+            if cel.is_empty() || (i == center && j == center) {
+                continue;
+            }
+            density += 1.0 - item.get_distance(cel, metric) / alpha;
+            area += 1.0;
+        }
+    }
+    if density <= 0.0 {
+        return 0.0;
+    }
+    density / (area * area)
+}
+
+impl<T: Object + Clone> Agent<T> {
+    pub fn update_agent<R: Rng>(&mut self, mapa: &Vision<T>, rng: &mut R) -> Vision<T> {
         let mut vision = self.see_map(mapa);
-        let old_state = self.state;
+        self.last_decision = None;
         match self.state {
-            AgentStates::CARRYING => self.update_carrying(&mut vision),
-            AgentStates::SEARCHING => self.update_searching(&mut vision),
-            AgentStates::FINISHING => self.update_finishing(&mut vision),
+            AgentStates::CARRYING => self.update_carrying(&mut vision, rng),
+            AgentStates::SEARCHING => self.update_searching(&mut vision, rng),
+            AgentStates::FINISHING => self.update_finishing(&mut vision, rng),
             AgentStates::DONE => (),
         }
-        let new_state = self.state;
-        let pos = self.pos;
-        if new_state != old_state {
-            match new_state {
-                AgentStates::CARRYING => {
-                    assert!(!self.backpack.is_empty());
-                    assert!(!mapa[pos.i][pos.j].is_empty());
-                    assert!(vision[1][1].is_empty());
+        // self.update_map(mapa, &vision);
+        self.move_agent(rng);
+        vision
+    }
+
+    /// Checks the last `update_agent`, given the position and state of the
+    /// agent before it, the map cell at that position before the vision was
+    /// written back and the returned vision.
+    pub fn check_update(
+        &self,
+        from: Point,
+        old_state: AgentStates,
+        cell: &T,
+        vision: &[Vec<T>],
+    ) -> Result<(), Invariant> {
+        let center = vision_center(vision);
+        match self.state {
+            AgentStates::CARRYING | AgentStates::FINISHING if self.backpack.is_empty() => {
+                return Err(Invariant::EmptyBackpack);
+            }
+            AgentStates::SEARCHING | AgentStates::DONE if !self.backpack.is_empty() => {
+                return Err(Invariant::FullBackpack);
+            }
+            _ => (),
+        }
+        match (old_state, self.state) {
+            (AgentStates::SEARCHING, AgentStates::CARRYING) => {
+                if cell.is_empty() {
+                    return Err(Invariant::PickedFromEmptyCell);
+                }
+                if !center.is_empty() {
+                    return Err(Invariant::CellNotUpdated);
                 }
-                AgentStates::SEARCHING => {
-                    assert!(self.backpack.is_empty());
-                    assert!(mapa[pos.i][pos.j].is_empty());
-                    assert!(!vision[1][1].is_empty());
+            }
+            (AgentStates::CARRYING, AgentStates::SEARCHING)
+            | (AgentStates::FINISHING, AgentStates::DONE) => {
+                if !cell.is_empty() {
+                    return Err(Invariant::DroppedOnOccupiedCell);
                 }
-                AgentStates::FINISHING => {
-                    // assert!(!self.backpack.is_empty());
-                    // assert!(!mapa[pos.i][pos.j].is_empty());
-                    // assert!(vision[1][1].is_empty());
+                if center.is_empty() {
+                    return Err(Invariant::CellNotUpdated);
                 }
-                AgentStates::DONE => {
-                    assert!(self.backpack.is_empty());
-                    assert!(mapa[pos.i][pos.j].is_empty());
-                    assert!(!vision[1][1].is_empty());
+            }
+            (from, to) if from != to => {
+                return Err(Invariant::UnexpectedTransition { from, to });
+            }
+            _ => {
+                if center.is_empty() != cell.is_empty() {
+                    return Err(Invariant::CellNotUpdated);
                 }
             }
         }
-        // self.update_map(mapa, &vision);
-        self.move_agent();
-        return vision;
+        if self.pos == from {
+            return Err(Invariant::DidNotMove);
+        }
+        if self.pos.i >= self.config.map_height || self.pos.j >= self.config.map_width {
+            return Err(Invariant::OutOfBounds(self.pos));
+        }
+        Ok(())
     }
 
     // fn show_vision(&self, vision: &Vision<T>){
@@ -118,6 +411,11 @@ impl<T: Object + Clone + Copy> Agent<T> {
     pub fn get_state(&self) -> AgentStates {
         self.state
     }
+    /// Density and probability of the pick-up or drop attempted during the
+    /// last `update_agent`, if any.
+    pub fn last_decision(&self) -> Option<Decision> {
+        self.last_decision
+    }
     pub fn new(pos: Point, config: AgentConfig) -> Agent<T> {
         let mut history: VecDeque<Point> = VecDeque::new();
         history.push_front(pos);
@@ -127,6 +425,7 @@ impl<T: Object + Clone + Copy> Agent<T> {
             backpack: T::clone_empty(),
             history,
             config,
+            last_decision: None,
         }
     }
 
@@ -153,145 +452,97 @@ impl<T: Object + Clone + Copy> Agent<T> {
     //     }
     // }
 
-    fn create_vision(&self) -> Vision<T> {
-        let mut vision = vec![];
-        let radius = self.config.vision_radius;
-        let vision_side = radius * 2 + 1;
-        for i in 0..vision_side {
-            vision.push(vec![]);
-            for _ in 0..vision_side {
-                vision[i].push(T::clone_empty());
-            }
-        }
-        vision
-    }
     fn see_map(&self, mapa: &Vision<T>) -> Vision<T> {
-        let mut vision: Vision<T> = self.create_vision();
-
-        let height = self.config.map_height;
-        let width = self.config.map_width;
-        let radius = self.config.vision_radius;
-
-        let side = radius * 2 + 1;
-
-        let pos = &self.pos;
-
-        for index_i in 0..side {
-            let i = if pos.i + index_i >= radius {
-                (pos.i + index_i - radius) % height
-            } else {
-                height + pos.i - radius + index_i
-            };
-            for index_j in 0..side {
-                let j = if pos.j + index_j >= radius {
-                    (pos.j + index_j - radius) % width
-                } else {
-                    width + pos.j + index_j - radius
-                };
-                vision[index_i][index_j] = mapa[i][j].clone();
-            }
-        }
-
-        return vision;
+        vision_window(
+            mapa,
+            self.pos,
+            self.config.vision_radius,
+            self.config.topology,
+            self.config.grid,
+        )
     }
-    fn move_agent(&mut self) {
+    fn move_agent<R: Rng>(&mut self, rng: &mut R) {
         let height = self.config.map_height;
         let width = self.config.map_width;
         let queue_size = self.config.queue_size;
-
-        let mut rng = rand::thread_rng();
-
-        let old_pos = &self.pos;
+        let topology = self.config.topology;
+        let grid = self.config.grid;
+
+        let old_pos = self.pos;
+        let target = |&(mut i, mut j): &(isize, isize)| {
+            // A step over a reflective edge becomes the step to the mirrored
+            // neighbour, so that it stays a step to a neighbour.
+            if topology == Topology::Reflective {
+                if topology.neighbor(old_pos.i, i, height).is_none() {
+                    i = -i;
+                }
+                if topology.neighbor(old_pos.j, j, width).is_none() {
+                    j = grid.mirror_column(old_pos.i, i, j);
+                }
+            }
+            let new_i = topology.neighbor(old_pos.i, i, height)?;
+            let new_j = topology.neighbor(old_pos.j, j, width)?;
+            Some(Point { i: new_i, j: new_j }).filter(|&new_pos| new_pos != old_pos)
+        };
+        let offsets = self.config.grid.neighbors(old_pos.i);
+        let mut reachable: Vec<Point> = vec![];
+        for new_pos in offsets.iter().filter_map(target) {
+            if !reachable.contains(&new_pos) {
+                reachable.push(new_pos);
+            }
+        }
+        let max_tries = queue_size.min(reachable.len());
 
         let mut new_pos: Point;
         let mut tries: Vec<Point> = vec![];
 
         loop {
-            let i: usize = rng.gen_range(0..=2);
-            let j: usize = rng.gen_range(0..=2);
-
-            let mut new_i: usize = old_pos.i + i;
-            if new_i > 0 {
-                new_i -= 1;
-            } else if new_i == 0 {
-                new_i = height - 1;
-            }
-            if new_i >= height {
-                new_i = new_i - height;
-            }
-
-            let mut new_j: usize = old_pos.j + j;
-            if new_j > 0 {
-                new_j -= 1;
-            } else if new_j == 0 {
-                new_j = width - 1;
-            }
-            if new_j >= width {
-                new_j = new_j - width;
-            }
-            new_pos = Point { i: new_i, j: new_j };
+            new_pos = match target(&offsets[rng.gen_range(0..offsets.len())]) {
+                Some(new_pos) => new_pos,
+                None => continue,
+            };
 
-            let tries_history = tries
-                .iter()
-                .find(|&point| point.i == new_pos.i && point.j == new_pos.j);
-            if !tries_history.is_none() {
+            if tries.contains(&new_pos) {
                 continue;
             }
-            tries.push(Point {
-                i: new_pos.i,
-                j: new_pos.j,
-            });
-
-            let history_result = self
-                .history
-                .iter()
-                .find(|&pos| pos.i == new_pos.i && pos.j == new_pos.j);
+            tries.push(new_pos);
 
-            if history_result.is_none() || tries.len() == queue_size {
+            if !self.history.contains(&new_pos) || tries.len() == max_tries {
                 break;
             }
         }
-        self.history.push_back(Point {
-            i: new_pos.i,
-            j: new_pos.j,
-        });
+        self.history.push_back(new_pos);
 
         if self.history.len() >= queue_size {
             self.history.pop_front();
         }
-        assert_ne!(self.pos, new_pos);
-        assert!(new_pos.i < height);
-        assert!(new_pos.j < width);
         self.pos = new_pos;
     }
 
-    fn update_carrying(&mut self, vision: &mut Vision<T>) {
+    fn update_carrying<R: Rng>(&mut self, vision: &mut Vision<T>, rng: &mut R) {
         let pos = self.get_self_pos();
-        if !self.should_drop(vision) {
+        if !self.should_drop(vision, rng) {
             return;
         }
-        vision[pos.i][pos.j] = self.backpack.clone();
-        self.backpack = T::clone_empty();
+        vision[pos.i][pos.j] = std::mem::replace(&mut self.backpack, T::clone_empty());
         self.state = AgentStates::SEARCHING;
         // println!("DROPPED");
     }
-    fn update_searching(&mut self, vision: &mut Vision<T>) {
+    fn update_searching<R: Rng>(&mut self, vision: &mut Vision<T>, rng: &mut R) {
         let pos = self.get_self_pos();
-        if !self.should_take(vision) {
+        if !self.should_take(vision, rng) {
             return;
         }
-        self.backpack = vision[pos.i][pos.j].clone();
-        vision[pos.i][pos.j] = T::clone_empty();
+        self.backpack = std::mem::replace(&mut vision[pos.i][pos.j], T::clone_empty());
         self.state = AgentStates::CARRYING;
         // println!("CARRING");
     }
-    fn update_finishing(&mut self, vision: &mut Vision<T>) {
+    fn update_finishing<R: Rng>(&mut self, vision: &mut Vision<T>, rng: &mut R) {
         let pos = self.get_self_pos();
-        if !self.should_take(vision) {
+        if !self.should_drop(vision, rng) {
             return;
         }
-        self.backpack = vision[pos.i][pos.j].clone();
-        vision[pos.i][pos.j] = T::clone_empty();
+        vision[pos.i][pos.j] = std::mem::replace(&mut self.backpack, T::clone_empty());
         self.state = AgentStates::DONE;
     }
     // fn count_objs_around(&self, vision: &Vision<T>) -> usize {
@@ -326,35 +577,22 @@ impl<T: Object + Clone + Copy> Agent<T> {
             j: radius,
         }
     }
+    /// Local density around the agent of the item it carries or, when
+    /// searching, of the item under it.
     fn get_density(&self, vision: &Vision<T>) -> f64 {
-        let mut density = 0.0;
-        let pos: &Point = &self.pos;
-        let radius = self.config.vision_radius;
-        let side = radius * 2 + 1;
-        let mut area = 0.0;
-        let alpha = 6.0;
-        for i in 0..side {
-            for j in 0..side {
-                if !vision[i][j].is_empty() && (i != pos.i || j != pos.j) {
-                    // let dist = Agent::get_distance(&self.backpack, &vision[i][j]);
-                    let dist = self.backpack.get_distance(&vision[i][j]);
-                    let dissim = 1.0 - (dist / alpha);
-                    // if dissim >= 0.0 {
-                    density += dissim;
-                    // }
-                    area += 1.0;
-                }
-            }
-        }
-        if density <= 0.0 {
-            return 0.0;
-        }
-        let f = density / (area * area);
-        assert!(f <= 1.0);
-        f
+        let item = match self.backpack.is_empty() {
+            true => vision_center(vision),
+            false => &self.backpack,
+        };
+        local_density(
+            item,
+            vision,
+            self.config.alpha,
+            &self.config.distance_metric,
+        )
     }
 
-    fn should_take(&self, vision: &mut Vision<T>) -> bool {
+    fn should_take<R: Rng>(&mut self, vision: &mut Vision<T>, rng: &mut R) -> bool {
         let pos = self.get_self_pos();
         if vision[pos.i][pos.j].is_empty() {
             return false;
@@ -362,38 +600,39 @@ impl<T: Object + Clone + Copy> Agent<T> {
         // assert_ne!(vision[pos.i][pos.j].x, 0.0);
         // assert_ne!(vision[pos.i][pos.j].y, 0.0);
         // assert_ne!(vision[pos.i][pos.j].group, 0);
-        let k1 = 0.35;
+        let k1 = self.config.k1;
         let density = self.get_density(vision);
-        let coeff = k1 / (k1 + density);
-        let prob = coeff * coeff;
-
-        let mut rng = rand::thread_rng();
+        let prob = self.config.decision_model.take_probability(density, k1);
+        self.last_decision = Some(Decision {
+            density,
+            probability: prob,
+        });
 
         let value = rng.gen_range(0f64..=1f64);
 
-        let decision = value <= prob;
-        decision
+        value <= prob
     }
 
-    fn should_drop(&self, vision: &mut Vision<T>) -> bool {
+    fn should_drop<R: Rng>(&mut self, vision: &mut Vision<T>, rng: &mut R) -> bool {
         let pos = self.get_self_pos();
         if !vision[pos.i][pos.j].is_empty() {
             return false;
         }
 
-        let k2 = 0.35;
+        let k2 = self.config.k2;
         let density = self.get_density(vision);
         // if density != 0.0 {
         //     println!("{}", density);
         // }
-        let coeff = density / (k2 + density);
-        let prob = coeff * coeff;
+        let prob = self.config.decision_model.drop_probability(density, k2);
+        self.last_decision = Some(Decision {
+            density,
+            probability: prob,
+        });
 
-        let mut rng = rand::thread_rng();
         let value = rng.gen_range(0f64..=1f64);
 
-        let decision = value <= prob;
-        decision
+        value <= prob
     }
 
     pub fn finish(&mut self) {
@@ -410,27 +649,215 @@ impl<T: Object + Clone + Copy> Agent<T> {
         self.state == AgentStates::FINISHING
     }
 
-    pub fn create_agents(
-        radius: usize,
-        qtd: usize,
-        map_height: usize,
-        map_width: usize,
-    ) -> Vec<Agent<T>> {
+    pub fn create_agents<R: Rng>(config: AgentConfig, qtd: usize, rng: &mut R) -> Vec<Agent<T>> {
         let mut agents: Vec<Agent<T>> = vec![];
-        let mut rng = rand::thread_rng();
         for _ in 0..qtd {
             let pos = Point {
-                i: rng.gen_range(0..map_height),
-                j: rng.gen_range(0..map_width),
+                i: rng.gen_range(0..config.map_height),
+                j: rng.gen_range(0..config.map_width),
             };
-            let config = AgentConfig {
-                vision_radius: radius,
-                map_height,
-                map_width,
-                queue_size: 8,
+            agents.push(Agent::new(pos, config.clone()));
+        }
+        agents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct Item {
+        value: f64,
+        group: u8,
+    }
+
+    impl Object for Item {
+        fn is_empty(&self) -> bool {
+            self.group == 0
+        }
+        fn clone_empty() -> Self {
+            Item {
+                value: 0.0,
+                group: 0,
+            }
+        }
+        fn get_distance(&self, other_obj: &Self, metric: &DistanceMetric) -> f64 {
+            metric.distance(&[self.value], &[other_obj.value])
+        }
+    }
+
+    fn run(seed: u64) -> Vec<(Point, u8)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut mapa: Vision<Item> = vec![vec![Item::clone_empty(); 10]; 10];
+        for (i, row) in mapa.iter_mut().enumerate() {
+            row[i] = Item {
+                value: i as f64,
+                group: 1 + (i % 2) as u8,
             };
-            agents.push(Agent::new(pos, config));
         }
-        return agents;
+        let config = AgentConfig::new(1, 10, 10, DEFAULT_K1, DEFAULT_K2, DEFAULT_ALPHA).unwrap();
+        let mut agents = Agent::create_agents(config, 4, &mut rng);
+        for _ in 0..200 {
+            for agent in agents.iter_mut() {
+                let pos = agent.get_pos();
+                let vision = agent.update_agent(&mapa, &mut rng);
+                mapa[pos.i][pos.j] = *vision_center(&vision);
+            }
+        }
+        agents
+            .iter()
+            .map(|agent| (agent.get_pos(), agent.backpack.group))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_run() {
+        assert_eq!(run(42), run(42));
+    }
+
+    #[test]
+    fn finishing_agent_drops_its_item() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let item = Item {
+            value: 1.0,
+            group: 1,
+        };
+        let mut mapa: Vision<Item> = vec![vec![Item::clone_empty(); 10]; 10];
+        for row in mapa.iter_mut() {
+            row[0] = item;
+        }
+        let config = AgentConfig::new(1, 10, 10, DEFAULT_K1, DEFAULT_K2, DEFAULT_ALPHA).unwrap();
+        let mut agent = Agent::new(Point { i: 0, j: 1 }, config);
+        agent.backpack = item;
+        agent.state = AgentStates::CARRYING;
+        agent.finish();
+        for _ in 0..10_000 {
+            if agent.get_state() == AgentStates::DONE {
+                break;
+            }
+            let pos = agent.get_pos();
+            let vision = agent.update_agent(&mapa, &mut rng);
+            mapa[pos.i][pos.j] = *vision_center(&vision);
+        }
+        assert!(agent.get_state() == AgentStates::DONE);
+        assert!(agent.backpack.is_empty());
+        let items = mapa.iter().flatten().filter(|cel| !cel.is_empty()).count();
+        assert_eq!(items, 11);
+    }
+
+    #[test]
+    fn density_skips_own_cell() {
+        let item = |value| Item { value, group: 1 };
+        let mut vision: Vision<Item> = vec![vec![Item::clone_empty(); 5]; 5];
+        vision[2][2] = item(1.0);
+        vision[0][0] = item(1.0);
+        vision[3][3] = item(4.0);
+        let config = AgentConfig::new(2, 7, 7, DEFAULT_K1, DEFAULT_K2, DEFAULT_ALPHA).unwrap();
+        let mut agent = Agent::new(Point { i: 3, j: 3 }, config);
+        assert!((agent.get_density(&vision) - 1.5 / 4.0).abs() < 1e-12);
+        agent.backpack = item(7.0);
+        assert!((agent.get_density(&vision) - 0.5 / 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn checks_pick_up() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let item = Item {
+            value: 1.0,
+            group: 1,
+        };
+        let mut mapa: Vision<Item> = vec![vec![Item::clone_empty(); 7]; 7];
+        mapa[3][3] = item;
+        let config = AgentConfig::new(2, 7, 7, DEFAULT_K1, DEFAULT_K2, DEFAULT_ALPHA).unwrap();
+        let from = Point { i: 3, j: 3 };
+        let mut agent = Agent::new(from, config);
+        let vision = agent.update_agent(&mapa, &mut rng);
+        assert!(agent.get_state() == AgentStates::CARRYING);
+        assert!(vision_center(&vision).is_empty());
+        let check = |vision: &Vision<Item>| {
+            agent.check_update(from, AgentStates::SEARCHING, &mapa[3][3], vision)
+        };
+        assert_eq!(check(&vision), Ok(()));
+        let mut stale = vision.clone();
+        stale[2][2] = item;
+        assert_eq!(check(&stale), Err(Invariant::CellNotUpdated));
+    }
+
+    #[test]
+    fn rejects_invalid_params() {
+        assert_eq!(
+            AgentConfig::new(1, 10, 10, 0.0, DEFAULT_K2, DEFAULT_ALPHA).err(),
+            Some(ConfigError::InvalidK1(0.0))
+        );
+        assert_eq!(
+            AgentConfig::new(1, 10, 10, DEFAULT_K1, -0.1, DEFAULT_ALPHA).err(),
+            Some(ConfigError::InvalidK2(-0.1))
+        );
+        assert!(matches!(
+            AgentConfig::new(1, 10, 10, DEFAULT_K1, DEFAULT_K2, f64::NAN),
+            Err(ConfigError::InvalidAlpha(_))
+        ));
+        let config = AgentConfig::new(1, 10, 10, DEFAULT_K1, DEFAULT_K2, DEFAULT_ALPHA).unwrap();
+        assert!(matches!(
+            config
+                .clone()
+                .with_distance_metric(DistanceMetric::Minkowski(0.5)),
+            Err(ConfigError::InvalidMinkowskiOrder(_))
+        ));
+        assert!(matches!(
+            config
+                .clone()
+                .with_decision_model(DecisionModelKind::Sigmoid { slope: 0.0 }),
+            Err(ConfigError::InvalidSigmoidSlope(_))
+        ));
+        assert!(matches!(
+            config.with_decision_model(DecisionModelKind::Sigmoid { slope: f64::NAN }),
+            Err(ConfigError::InvalidSigmoidSlope(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let point = Point { i: 3, j: 7 };
+        let json = serde_json::to_string(&point).unwrap();
+        assert_eq!(serde_json::from_str::<Point>(&json).unwrap(), point);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mapa: Vision<Item> = vec![vec![Item::clone_empty(); 10]; 10];
+        let config = AgentConfig::new(1, 10, 10, DEFAULT_K1, DEFAULT_K2, DEFAULT_ALPHA).unwrap();
+        let mut agent = Agent::new(point, config);
+        agent.backpack = Item {
+            value: 2.5,
+            group: 2,
+        };
+        agent.state = AgentStates::CARRYING;
+        for _ in 0..3 {
+            agent.update_agent(&mapa, &mut rng);
+        }
+        let json = serde_json::to_string(&agent).unwrap();
+        let parsed: Agent<Item> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.pos, agent.pos);
+        assert_eq!(parsed.state, agent.state);
+        assert_eq!(parsed.backpack, agent.backpack);
+        assert_eq!(parsed.history, agent.history);
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+
+        let config = AgentConfig::new(2, 10, 20, 0.1, 0.2, 3.0)
+            .unwrap()
+            .with_decision_model(DecisionModelKind::Sigmoid { slope: 5.0 })
+            .unwrap()
+            .with_distance_metric(DistanceMetric::Minkowski(3.0))
+            .unwrap();
+        let json = serde_json::to_string(&config).unwrap();
+        let parsed: AgentConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        assert!(matches!(
+            parsed.distance_metric,
+            DistanceMetric::Minkowski(p) if p == 3.0
+        ));
     }
 }
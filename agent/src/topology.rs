@@ -0,0 +1,56 @@
+/// How the edges of the map behave.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Topology {
+    /// Opposite edges are joined, so the map wraps around.
+    #[default]
+    Torus,
+    /// The map ends at its edges: agents see nothing beyond them and cannot
+    /// cross them.
+    Bounded,
+    /// Like `Bounded`, but a step over an edge bounces back off it as off a
+    /// mirror.
+    Reflective,
+}
+
+impl Topology {
+    /// Index of the cell `offset` cells away from `index` along an axis of
+    /// `size` cells, or `None` beyond the edge of a non-wrapping map.
+    pub fn neighbor(&self, index: usize, offset: isize, size: usize) -> Option<usize> {
+        let target = index as isize + offset;
+        match self {
+            Topology::Torus => Some(target.rem_euclid(size as isize) as usize),
+            Topology::Bounded | Topology::Reflective => (0..size as isize)
+                .contains(&target)
+                .then_some(target as usize),
+        }
+    }
+
+    /// Signed number of cells from `from` to `to` along an axis of `size`
+    /// cells, going the short way around the edge when the map wraps.
+    pub fn offset(&self, from: usize, to: usize, size: usize) -> isize {
+        let diff = to as isize - from as isize;
+        match self {
+            Topology::Torus if diff.unsigned_abs() * 2 > size => {
+                diff - diff.signum() * size as isize
+            }
+            _ => diff,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edges() {
+        assert_eq!(Topology::Torus.neighbor(0, -2, 5), Some(3));
+        assert_eq!(Topology::Bounded.neighbor(0, -2, 5), None);
+        assert_eq!(Topology::Reflective.neighbor(4, 1, 5), None);
+        assert_eq!(Topology::Torus.offset(0, 4, 5), -1);
+        assert_eq!(Topology::Torus.offset(4, 0, 5), 1);
+        assert_eq!(Topology::Bounded.offset(0, 4, 5), 4);
+    }
+}
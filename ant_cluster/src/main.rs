@@ -1,22 +1,23 @@
-use rand::Rng;
+use clap::{error::ErrorKind, CommandFactory, FromArgMatches, Parser};
+use cli::{ExperimentFile, SimArgs};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{collections::VecDeque, fmt::Display};
 
-const MAPA_HEIGHT: usize = 40;
-const MAPA_WIDTH: usize = 40;
+const DEFAULT_MAPA_HEIGHT: usize = 40;
+const DEFAULT_MAPA_WIDTH: usize = 40;
 
 const QTD_OBJS: usize = 150;
-const QTD_AGENTS: usize = 20;
+const DEFAULT_QTD_AGENTS: usize = 20;
 
 type CarryValueType = u32;
 type MapaDef = Vec<Vec<CarryValueType>>;
 
-fn init_objs() -> MapaDef {
-    let mut mapa = vec![vec![0; MAPA_WIDTH]; MAPA_HEIGHT];
-    let mut rng = rand::thread_rng();
+fn init_objs<R: Rng>(mapa_height: usize, mapa_width: usize, rng: &mut R) -> MapaDef {
+    let mut mapa = vec![vec![0; mapa_width]; mapa_height];
     let mut qtd_done = 0;
     while qtd_done < QTD_OBJS {
-        let i: usize = rng.gen_range(0..MAPA_HEIGHT);
-        let j: usize = rng.gen_range(0..MAPA_WIDTH);
+        let i: usize = rng.gen_range(0..mapa_height);
+        let j: usize = rng.gen_range(0..mapa_width);
         // let value: u32 = rng.gen_range(1u32..=9u32);
         let mapa_pos = mapa[i][j];
         if mapa_pos != 0 {
@@ -25,11 +26,11 @@ fn init_objs() -> MapaDef {
         mapa[i][j] = 1;
         qtd_done += 1;
     }
-    return mapa;
+    mapa
 }
 
 fn show_mapa(mapa: &MapaDef) {
-    let divisor = "-".repeat(MAPA_WIDTH * 4 + 1);
+    let divisor = "-".repeat(mapa[0].len() * 4 + 1);
     println!("{}", divisor);
     for row in mapa {
         for cel in row {
@@ -39,13 +40,14 @@ fn show_mapa(mapa: &MapaDef) {
             }
             print!("| {} ", cel);
         }
-        print!("|\n");
+        println!("|");
     }
     println!("{}", divisor);
     println!();
 }
 
 #[derive(PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 enum AgentStates {
     CARRYING,
     SEARCHING,
@@ -88,14 +90,14 @@ struct Agent {
 }
 
 impl Agent {
-    pub fn update_agent(&mut self, mapa: &mut MapaDef) {
+    pub fn update_agent<R: Rng>(&mut self, mapa: &mut MapaDef, rng: &mut R) {
         match self.state {
-            AgentStates::CARRYING => self.update_carrying(mapa),
-            AgentStates::SEARCHING => self.update_searching(mapa),
-            AgentStates::FINISHING => self.update_carrying(mapa),
+            AgentStates::CARRYING => self.update_carrying(mapa, rng),
+            AgentStates::SEARCHING => self.update_searching(mapa, rng),
+            AgentStates::FINISHING => self.update_carrying(mapa, rng),
             AgentStates::DONE => (),
         }
-        self.move_agent();
+        self.move_agent(mapa, rng);
         // println!(
         //     "New Agent state {} {} {} {}",
         //     self.pos.i, self.pos.j, self.backpack, self.state
@@ -123,9 +125,9 @@ impl Agent {
         }
     }
 
-    fn move_agent(&mut self) {
+    fn move_agent<R: Rng>(&mut self, mapa: &MapaDef, rng: &mut R) {
         const MAX_QUEUE_SIZE: usize = 8;
-        let mut rng = rand::thread_rng();
+        let (mapa_height, mapa_width) = (mapa.len(), mapa[0].len());
         let old_pos = &self.pos;
         let mut new_pos: Point;
         let mut tries: Vec<Point> = vec![];
@@ -136,27 +138,27 @@ impl Agent {
             if new_i > 0 {
                 new_i -= 1;
             } else if new_i == 0 {
-                new_i = MAPA_HEIGHT - 1;
+                new_i = mapa_height - 1;
             }
-            if new_i >= MAPA_HEIGHT {
-                new_i = new_i - MAPA_HEIGHT;
+            if new_i >= mapa_height {
+                new_i -= mapa_height;
             }
 
             let mut new_j: usize = old_pos.j + j;
             if new_j > 0 {
                 new_j -= 1;
             } else if new_j == 0 {
-                new_j = MAPA_WIDTH - 1;
+                new_j = mapa_width - 1;
             }
-            if new_j >= MAPA_WIDTH {
-                new_j = new_j - MAPA_WIDTH;
+            if new_j >= mapa_width {
+                new_j -= mapa_width;
             }
             new_pos = Point { i: new_i, j: new_j };
 
             let tries_history = tries
                 .iter()
                 .find(|&point| point.i == new_pos.i && point.j == new_pos.j);
-            if !tries_history.is_none() {
+            if tries_history.is_some() {
                 continue;
             }
             tries.push(Point {
@@ -192,8 +194,8 @@ impl Agent {
             self.history.pop_front();
         }
         assert_ne!(self.pos, new_pos);
-        assert!(new_pos.i < MAPA_HEIGHT);
-        assert!(new_pos.j < MAPA_WIDTH);
+        assert!(new_pos.i < mapa_height);
+        assert!(new_pos.j < mapa_width);
         // let mut x_diff = new_pos.i as i64 - self.pos.i as i64;
         // if x_diff >= 19 {
         //     x_diff = -1;
@@ -211,28 +213,29 @@ impl Agent {
     }
 
     fn count_objs_around(&self, mapa: &MapaDef) -> usize {
+        let (mapa_height, mapa_width) = (mapa.len(), mapa[0].len());
         let mut count = 0;
         let pos: &Point = &self.pos;
         let radius = self.radius;
         let side = radius * 2 + 1;
         for index_i in 0..side {
             let i = if pos.i + index_i >= radius {
-                (pos.i + index_i - radius) % MAPA_HEIGHT
+                (pos.i + index_i - radius) % mapa_height
             } else {
-                MAPA_HEIGHT + pos.i - radius + index_i
+                mapa_height + pos.i - radius + index_i
             };
             for index_j in 0..side {
                 let j = if pos.j + index_j >= radius {
-                    (pos.j + index_j - radius) % MAPA_WIDTH
+                    (pos.j + index_j - radius) % mapa_width
                 } else {
-                    MAPA_WIDTH + pos.j + index_j - radius
+                    mapa_width + pos.j + index_j - radius
                 };
                 if mapa[i][j] != 0 && (i != pos.i || j != pos.j) {
                     count += 1;
                 }
             }
         }
-        return count;
+        count
     }
 
     fn probability(qtd_objs: usize, radius: usize) -> f64 {
@@ -241,9 +244,9 @@ impl Agent {
         qtd_objs as f64 / qtd_cels as f64
     }
 
-    fn update_searching(&mut self, mapa: &mut MapaDef) {
+    fn update_searching<R: Rng>(&mut self, mapa: &mut MapaDef, rng: &mut R) {
         let pos = &self.pos;
-        if self.should_take(mapa) {
+        if self.should_take(mapa, rng) {
             let old = mapa[pos.i][pos.j];
             self.backpack = mapa[pos.i][pos.j];
             mapa[pos.i][pos.j] = 0;
@@ -255,7 +258,7 @@ impl Agent {
         }
     }
 
-    fn should_take(&self, mapa: &mut MapaDef) -> bool {
+    fn should_take<R: Rng>(&self, mapa: &mut MapaDef, rng: &mut R) -> bool {
         let pos = &self.pos;
         if mapa[pos.i][pos.j] == 0 {
             return false;
@@ -263,17 +266,14 @@ impl Agent {
         let qtd_objs = self.count_objs_around(mapa);
         let prob = Agent::probability(qtd_objs, self.radius);
 
-        let mut rng = rand::thread_rng();
-
         let value = rng.gen_range(0f64..=1f64);
 
-        let decision = value >= prob;
-        decision
+        value >= prob
     }
 
-    fn update_carrying(&mut self, mapa: &mut MapaDef) {
+    fn update_carrying<R: Rng>(&mut self, mapa: &mut MapaDef, rng: &mut R) {
         let pos = &self.pos;
-        if !self.should_drop(mapa) {
+        if !self.should_drop(mapa, rng) {
             self.rounds_carrying += 1;
             return;
         }
@@ -291,7 +291,7 @@ impl Agent {
         // println!("DROPPED");
     }
 
-    fn should_drop(&self, mapa: &mut MapaDef) -> bool {
+    fn should_drop<R: Rng>(&self, mapa: &mut MapaDef, rng: &mut R) -> bool {
         let pos = &self.pos;
         if mapa[pos.i][pos.j] != 0 {
             return false;
@@ -300,52 +300,107 @@ impl Agent {
         let qtd_objs = self.count_objs_around(mapa);
         let prob = Agent::probability(qtd_objs, self.radius);
 
-        let mut rng = rand::thread_rng();
-
         let value = rng.gen_range(0f64..=1f64);
 
-        let decision = value <= prob;
-        decision // || self.rounds_carrying > 10
+        value <= prob // || self.rounds_carrying > 10
     }
 }
 
 // fn agent_worker(initial_x: usize, initial_y: usize) {}
 
-fn create_agents(radius: usize) -> Vec<Agent> {
+fn create_agents<R: Rng>(
+    radius: usize,
+    qtd: usize,
+    mapa_height: usize,
+    mapa_width: usize,
+    rng: &mut R,
+) -> Vec<Agent> {
     let mut agents: Vec<Agent> = vec![];
-    let mut rng = rand::thread_rng();
-    for _ in 0..QTD_AGENTS {
+    for _ in 0..qtd {
         let pos = Point {
-            i: rng.gen_range(0..MAPA_HEIGHT),
-            j: rng.gen_range(0..MAPA_WIDTH),
+            i: rng.gen_range(0..mapa_height),
+            j: rng.gen_range(0..mapa_width),
         };
         agents.push(Agent::new(pos.i, pos.j, radius));
     }
-    return agents;
+    agents
+}
+
+/// Ant clustering of identical items, without data or similarity. The map
+/// is 40x40 cells with 20 agents unless set otherwise
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    sim: SimArgs,
 }
 
+/// `SimArgs` fields this model has a use for: every item is the same, so
+/// there is nothing to compare.
+const FLAGS: [&str; 7] = [
+    "config", "iters", "height", "width", "agents", "radius", "seed",
+];
+/// Experiment file keys of the settings in [`FLAGS`].
+const KEYS: [&str; 6] = [
+    "max_iters",
+    "mapa_height",
+    "mapa_width",
+    "qtd_agents",
+    "seed",
+    "agent.vision_radius",
+];
+
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    let radius: usize = if args.len() > 1 {
-        args[1].parse::<usize>().unwrap_or(1)
-    } else {
-        1
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let exit = |kind, message: String| -> ! { Cli::command().error(kind, message).exit() };
+    let unsupported = cli
+        .sim
+        .unsupported(&matches, &FLAGS, &KEYS)
+        .unwrap_or_else(|err| exit(ErrorKind::ValueValidation, err.to_string()));
+    if !unsupported.is_empty() {
+        exit(
+            ErrorKind::ArgumentConflict,
+            format!(
+                "ant_cluster places identical items and does not use: {}",
+                unsupported.join(", ")
+            ),
+        );
+    }
+    let mut file = match &cli.sim.config {
+        Some(path) => ExperimentFile::load(path)
+            .unwrap_or_else(|err| exit(ErrorKind::ValueValidation, err.to_string())),
+        None => ExperimentFile::default(),
     };
-    let max_iters: usize = if args.len() > 2 {
-        args[2].parse::<usize>().unwrap_or(100_000)
-    } else {
-        100_000
+    file.mapa_height.get_or_insert(DEFAULT_MAPA_HEIGHT);
+    file.mapa_width.get_or_insert(DEFAULT_MAPA_WIDTH);
+    file.qtd_agents.get_or_insert(DEFAULT_QTD_AGENTS);
+    let config = cli
+        .sim
+        .to_config_with(file)
+        .unwrap_or_else(|err| exit(ErrorKind::ValueValidation, err.to_string()));
+    if let Err(err) = config.check(QTD_OBJS) {
+        exit(ErrorKind::ValueValidation, err.to_string());
+    }
+    let (mapa_height, mapa_width) = (config.mapa_height, config.mapa_width);
+    let radius = config.agent_vision_radius;
+    let max_iters = config.max_iters;
+    println!(
+        "Config Radius {} Iters {} Seed {:?}",
+        radius, max_iters, config.seed
+    );
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
     };
-    println!("Config Radius {} Iters {}", radius, max_iters);
-    let mut mapa = init_objs();
-    let mut agents = create_agents(radius);
+    let mut mapa = init_objs(mapa_height, mapa_width, &mut rng);
+    let mut agents = create_agents(radius, config.qtd_agents, mapa_height, mapa_width, &mut rng);
     show_mapa(&mapa);
     for _ in 0..max_iters {
         // if iter % 10000 == 0 {
         //     println!("Iteração: {}", iter);
         // }
         for agent in agents.iter_mut() {
-            agent.update_agent(&mut mapa);
+            agent.update_agent(&mut mapa, &mut rng);
         }
     }
     for agent in agents.iter_mut() {
@@ -365,7 +420,7 @@ fn main() {
             break;
         }
         for agent in remaining {
-            agent.update_agent(&mut mapa);
+            agent.update_agent(&mut mapa, &mut rng);
         }
         // if iter % 1000 == 0 {
         //     println!("Iteração Extra: {}", iter);
@@ -41,7 +41,7 @@ fn init_objs() -> MapaDef {
         }
         qtd_done += 1;
     }
-    return mapa;
+    mapa
 }
 
 fn show_mapa(mapa: &MapaDef) {
@@ -52,12 +52,13 @@ fn show_mapa(mapa: &MapaDef) {
             let value = *cel.read().unwrap();
             print!("| {} ", value);
         }
-        print!("|\n");
+        println!("|");
     }
     println!("{}", divisor);
     println!();
 }
 
+#[allow(clippy::upper_case_acronyms)]
 enum AgentStates {
     CARRYING,
     SEARCHING,
@@ -126,16 +127,12 @@ impl Agent {
             let x: usize = rng.gen_range(0..=2);
             let y: usize = rng.gen_range(0..=2);
             let mut new_x: usize = old_pos.x + x;
-            if new_x > 0 {
-                new_x -= 1;
-            }
+            new_x = new_x.saturating_sub(1);
             if new_x >= MAPA_WIDTH {
                 new_x = MAPA_WIDTH - 1;
             }
             let mut new_y: usize = old_pos.y + y;
-            if new_y > 0 {
-                new_y -= 1;
-            }
+            new_y = new_y.saturating_sub(1);
             if new_y >= MAPA_HEIGHT {
                 new_y = MAPA_HEIGHT - 1;
             }
@@ -179,7 +176,7 @@ impl Agent {
                 }
             }
         }
-        return count;
+        count
     }
 
     fn probability(&self, qtd_objs: usize, radius: usize) -> f64 {
@@ -217,8 +214,7 @@ impl Agent {
 
         let value = rng.gen_range(0f64..=1f64);
 
-        let decision = value >= prob;
-        decision
+        value >= prob
     }
 
     fn update_carrying(&mut self, mapa: &MapaDef) {
@@ -260,8 +256,7 @@ impl Agent {
 
         let value = rng.gen_range(0f64..=1f64);
 
-        let decision = value <= prob;
-        decision // || self.rounds_carrying > 10
+        value <= prob // || self.rounds_carrying > 10
     }
 }
 
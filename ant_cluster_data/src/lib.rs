@@ -2,36 +2,53 @@ use rand::Rng;
 use std::{collections::VecDeque, fmt::Display};
 use object::Object;
 
-use data_retrieve::{get_data, Data, DATA_1_FP};
+use data_retrieve::Data;
 
-pub const MAPA_HEIGHT: usize = 70;
-pub const MAPA_WIDTH: usize = 90;
+pub const DEFAULT_MAPA_HEIGHT: usize = 70;
+pub const DEFAULT_MAPA_WIDTH: usize = 90;
 
-pub const QTD_AGENTS: usize = 20;
+pub const DEFAULT_QTD_AGENTS: usize = 20;
+
+pub const DEFAULT_K1: f64 = 0.25;
+pub const DEFAULT_K2: f64 = 0.20;
 
 pub type CarryValueType = Data;
 pub type MapaDef = Vec<Vec<CarryValueType>>;
 
-pub fn init_map() -> MapaDef {
+/// Vision radius, pick-up (`k1`) and drop (`k2`) constants and
+/// dissimilarity scale (`alpha`) of an agent.
+#[derive(Clone, Copy)]
+pub struct AgentParams {
+    pub radius: usize,
+    pub k1: f64,
+    pub k2: f64,
+    pub alpha: f64,
+}
+
+pub fn init_map(mapa_height: usize, mapa_width: usize) -> MapaDef {
     let mut mapa = vec![];
-    for i in 0..MAPA_HEIGHT {
+    for i in 0..mapa_height {
         mapa.push(vec![]);
-        for _ in 0..MAPA_WIDTH {
+        for _ in 0..mapa_width {
             mapa[i].push(Data::clone_empty());
         }
     }
-    return mapa;
+    mapa
 }
 
-pub fn init_objs() -> MapaDef {
-    let mut mapa = init_map();
-    let mut rng = rand::thread_rng();
+/// Places `data` on random cells of an empty map, which must have at least
+/// as many cells as there are items.
+pub fn init_objs<R: Rng>(
+    data: &[Data],
+    mapa_height: usize,
+    mapa_width: usize,
+    rng: &mut R,
+) -> MapaDef {
+    let mut mapa = init_map(mapa_height, mapa_width);
     let mut qtd_done = 0;
-    let data = get_data(DATA_1_FP);
-    assert_eq!(data.len(), 400);
     while qtd_done < data.len() {
-        let i: usize = rng.gen_range(0..MAPA_HEIGHT);
-        let j: usize = rng.gen_range(0..MAPA_WIDTH);
+        let i: usize = rng.gen_range(0..mapa_height);
+        let j: usize = rng.gen_range(0..mapa_width);
         // let value: u32 = rng.gen_range(1u32..=9u32);
         let mapa_pos = mapa[i][j];
         if !mapa_pos.is_empty() {
@@ -42,11 +59,11 @@ pub fn init_objs() -> MapaDef {
         mapa[i][j].group = data[qtd_done].group;
         qtd_done += 1;
     }
-    return mapa;
+    mapa
 }
 
 pub fn show_mapa(mapa: &MapaDef) {
-    let divisor = "-".repeat(MAPA_WIDTH * 4 + 1);
+    let divisor = "-".repeat(mapa[0].len() * 4 + 1);
     println!("{}", divisor);
     for row in mapa {
         for cel in row {
@@ -56,7 +73,7 @@ pub fn show_mapa(mapa: &MapaDef) {
             }
             print!("| {} ", cel.group);
         }
-        print!("|\n");
+        println!("|");
     }
     println!("{}", divisor);
     println!();
@@ -101,24 +118,24 @@ pub struct Agent {
     backpack: CarryValueType,
     history: VecDeque<Point>,
     rounds_carrying: usize,
-    radius: usize,
+    params: AgentParams,
 }
 
 impl Agent {
-    pub fn update_agent(&mut self, mapa: &mut MapaDef) {
+    pub fn update_agent<R: Rng>(&mut self, mapa: &mut MapaDef, rng: &mut R) {
         match self.state {
-            AgentStates::CARRYING => self.update_carrying(mapa),
-            AgentStates::SEARCHING => self.update_searching(mapa),
-            AgentStates::FINISHING => self.update_carrying(mapa),
+            AgentStates::CARRYING => self.update_carrying(mapa, rng),
+            AgentStates::SEARCHING => self.update_searching(mapa, rng),
+            AgentStates::FINISHING => self.update_carrying(mapa, rng),
             AgentStates::DONE => (),
         }
-        self.move_agent();
+        self.move_agent(mapa, rng);
         // println!(
         //     "New Agent state {} {} {} {}",
         //     self.pos.i, self.pos.j, self.backpack, self.state
         // );
     }
-    pub fn new(initial_i: usize, initial_j: usize, radius: usize) -> Agent {
+    pub fn new(initial_i: usize, initial_j: usize, params: AgentParams) -> Agent {
         let mut history: VecDeque<Point> = VecDeque::new();
         let point = Point {
             i: initial_i,
@@ -136,13 +153,13 @@ impl Agent {
             backpack: Data::clone_empty(),
             history,
             rounds_carrying: 0,
-            radius,
+            params,
         }
     }
 
-    fn move_agent(&mut self) {
+    fn move_agent<R: Rng>(&mut self, mapa: &MapaDef, rng: &mut R) {
         const MAX_QUEUE_SIZE: usize = 8;
-        let mut rng = rand::thread_rng();
+        let (mapa_height, mapa_width) = (mapa.len(), mapa[0].len());
         let old_pos = &self.pos;
         let mut new_pos: Point;
         let mut tries: Vec<Point> = vec![];
@@ -153,27 +170,27 @@ impl Agent {
             if new_i > 0 {
                 new_i -= 1;
             } else if new_i == 0 {
-                new_i = MAPA_HEIGHT - 1;
+                new_i = mapa_height - 1;
             }
-            if new_i >= MAPA_HEIGHT {
-                new_i = new_i - MAPA_HEIGHT;
+            if new_i >= mapa_height {
+                new_i -= mapa_height;
             }
 
             let mut new_j: usize = old_pos.j + j;
             if new_j > 0 {
                 new_j -= 1;
             } else if new_j == 0 {
-                new_j = MAPA_WIDTH - 1;
+                new_j = mapa_width - 1;
             }
-            if new_j >= MAPA_WIDTH {
-                new_j = new_j - MAPA_WIDTH;
+            if new_j >= mapa_width {
+                new_j -= mapa_width;
             }
             new_pos = Point { i: new_i, j: new_j };
 
             let tries_history = tries
                 .iter()
                 .find(|&point| point.i == new_pos.i && point.j == new_pos.j);
-            if !tries_history.is_none() {
+            if tries_history.is_some() {
                 continue;
             }
             tries.push(Point {
@@ -209,8 +226,8 @@ impl Agent {
             self.history.pop_front();
         }
         assert_ne!(self.pos, new_pos);
-        assert!(new_pos.i < MAPA_HEIGHT);
-        assert!(new_pos.j < MAPA_WIDTH);
+        assert!(new_pos.i < mapa_height);
+        assert!(new_pos.j < mapa_width);
         // let mut x_diff = new_pos.i as i64 - self.pos.i as i64;
         // if x_diff >= 19 {
         //     x_diff = -1;
@@ -235,23 +252,24 @@ impl Agent {
     }
 
     fn get_density(&self, mapa: &MapaDef) -> f64 {
+        let (mapa_height, mapa_width) = (mapa.len(), mapa[0].len());
         let mut density = 0.0;
         let pos: &Point = &self.pos;
-        let radius = self.radius;
+        let radius = self.params.radius;
         let side = radius * 2 + 1;
         let mut area = 0.0;
-        let alpha = 6.0;
+        let alpha = self.params.alpha;
         for index_i in 0..side {
             let i = if pos.i + index_i >= radius {
-                (pos.i + index_i - radius) % MAPA_HEIGHT
+                (pos.i + index_i - radius) % mapa_height
             } else {
-                MAPA_HEIGHT + pos.i - radius + index_i
+                mapa_height + pos.i - radius + index_i
             };
             for index_j in 0..side {
                 let j = if pos.j + index_j >= radius {
-                    (pos.j + index_j - radius) % MAPA_WIDTH
+                    (pos.j + index_j - radius) % mapa_width
                 } else {
-                    MAPA_WIDTH + pos.j + index_j - radius
+                    mapa_width + pos.j + index_j - radius
                 };
                 if !mapa[i][j].is_empty() && (i != pos.i || j != pos.j) {
                     let dist = Agent::get_distance(&self.backpack, &mapa[i][j]);
@@ -271,9 +289,9 @@ impl Agent {
         f
     }
 
-    fn update_searching(&mut self, mapa: &mut MapaDef) {
+    fn update_searching<R: Rng>(&mut self, mapa: &mut MapaDef, rng: &mut R) {
         let pos = &self.pos;
-        if !self.should_take(mapa) {
+        if !self.should_take(mapa, rng) {
             return;
         }
         self.backpack.x = mapa[pos.i][pos.j].x;
@@ -288,7 +306,7 @@ impl Agent {
         // println!("TOOK");
     }
 
-    fn should_take(&self, mapa: &mut MapaDef) -> bool {
+    fn should_take<R: Rng>(&self, mapa: &mut MapaDef, rng: &mut R) -> bool {
         let pos = &self.pos;
         if mapa[pos.i][pos.j].is_empty() {
             return false;
@@ -296,22 +314,19 @@ impl Agent {
         assert_ne!(mapa[pos.i][pos.j].x, 0.0);
         assert_ne!(mapa[pos.i][pos.j].y, 0.0);
         assert_ne!(mapa[pos.i][pos.j].group, 0);
-        let k1 = 0.25;
+        let k1 = self.params.k1;
         let density = self.get_density(mapa);
         let coeff = k1 / (k1 + density);
         let prob = coeff * coeff;
 
-        let mut rng = rand::thread_rng();
-
         let value = rng.gen_range(0f64..=1f64);
 
-        let decision = value <= prob;
-        decision
+        value <= prob
     }
 
-    fn update_carrying(&mut self, mapa: &mut MapaDef) {
+    fn update_carrying<R: Rng>(&mut self, mapa: &mut MapaDef, rng: &mut R) {
         let pos = &self.pos;
-        if !self.should_drop(mapa) {
+        if !self.should_drop(mapa, rng) {
             self.rounds_carrying += 1;
             return;
         }
@@ -331,13 +346,13 @@ impl Agent {
         // println!("DROPPED");
     }
 
-    fn should_drop(&self, mapa: &mut MapaDef) -> bool {
+    fn should_drop<R: Rng>(&self, mapa: &mut MapaDef, rng: &mut R) -> bool {
         let pos = &self.pos;
         if !mapa[pos.i][pos.j].is_empty() {
             return false;
         }
 
-        let k2 = 0.20;
+        let k2 = self.params.k2;
         let density = self.get_density(mapa);
         // if density != 0.0 {
         //     println!("{}", density);
@@ -345,25 +360,28 @@ impl Agent {
         let coeff = density / (k2 + density);
         let prob = coeff * coeff;
 
-        let mut rng = rand::thread_rng();
         let value = rng.gen_range(0f64..=1f64);
 
-        let decision = value <= prob;
-        decision
+        value <= prob
     }
 }
 
 // fn agent_worker(initial_x: usize, initial_y: usize) {}
 
-pub fn create_agents(radius: usize) -> Vec<Agent> {
+pub fn create_agents<R: Rng>(
+    params: AgentParams,
+    qtd: usize,
+    mapa_height: usize,
+    mapa_width: usize,
+    rng: &mut R,
+) -> Vec<Agent> {
     let mut agents: Vec<Agent> = vec![];
-    let mut rng = rand::thread_rng();
-    for _ in 0..QTD_AGENTS {
+    for _ in 0..qtd {
         let pos = Point {
-            i: rng.gen_range(0..MAPA_HEIGHT),
-            j: rng.gen_range(0..MAPA_WIDTH),
+            i: rng.gen_range(0..mapa_height),
+            j: rng.gen_range(0..mapa_width),
         };
-        agents.push(Agent::new(pos.i, pos.j, radius));
+        agents.push(Agent::new(pos.i, pos.j, params));
     }
-    return agents;
+    agents
 }
@@ -1,20 +1,99 @@
-use ant_cluster_data::{create_agents, init_objs, show_mapa, AgentStates, Agent};
+use clap::{error::ErrorKind, CommandFactory, FromArgMatches, Parser};
+use cli::{ExperimentFile, SimArgs};
+use data_retrieve::get_data;
+use map::DataSource;
+use rand::{rngs::StdRng, SeedableRng};
+
+use ant_cluster_data::{
+    create_agents, init_objs, show_mapa, Agent, AgentParams, AgentStates, DEFAULT_K1, DEFAULT_K2,
+    DEFAULT_MAPA_HEIGHT, DEFAULT_MAPA_WIDTH, DEFAULT_QTD_AGENTS,
+};
+
+/// Ant clustering of a labelled two-dimensional data set. The map is 70x90
+/// cells with 20 agents, k1 0.25 and k2 0.2 unless set otherwise
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    sim: SimArgs,
+}
+
+/// `SimArgs` fields this model has a use for: it compares the labelled x
+/// and y of the items with the Euclidean distance and the Lumer–Faieta
+/// probabilities, on a torus.
+const FLAGS: [&str; 11] = [
+    "config", "iters", "height", "width", "agents", "radius", "seed", "k1", "k2", "alpha", "data",
+];
+/// Experiment file keys of the settings in [`FLAGS`].
+const KEYS: [&str; 10] = [
+    "max_iters",
+    "mapa_height",
+    "mapa_width",
+    "qtd_agents",
+    "seed",
+    "data",
+    "agent.vision_radius",
+    "agent.k1",
+    "agent.k2",
+    "agent.alpha",
+];
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    let radius: usize = if args.len() > 1 {
-        args[1].parse::<usize>().unwrap_or(1)
-    } else {
-        1
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let exit = |kind, message: String| -> ! { Cli::command().error(kind, message).exit() };
+    let unsupported = cli
+        .sim
+        .unsupported(&matches, &FLAGS, &KEYS)
+        .unwrap_or_else(|err| exit(ErrorKind::ValueValidation, err.to_string()));
+    if !unsupported.is_empty() {
+        exit(
+            ErrorKind::ArgumentConflict,
+            format!(
+                "ant_cluster_data clusters labelled x/y items and does not use: {}",
+                unsupported.join(", ")
+            ),
+        );
+    }
+    let mut file = match &cli.sim.config {
+        Some(path) => ExperimentFile::load(path)
+            .unwrap_or_else(|err| exit(ErrorKind::ValueValidation, err.to_string())),
+        None => ExperimentFile::default(),
+    };
+    file.mapa_height.get_or_insert(DEFAULT_MAPA_HEIGHT);
+    file.mapa_width.get_or_insert(DEFAULT_MAPA_WIDTH);
+    file.qtd_agents.get_or_insert(DEFAULT_QTD_AGENTS);
+    file.agent.k1.get_or_insert(DEFAULT_K1);
+    file.agent.k2.get_or_insert(DEFAULT_K2);
+    let config = cli
+        .sim
+        .to_config_with(file)
+        .unwrap_or_else(|err| exit(ErrorKind::ValueValidation, err.to_string()));
+    let data = match &config.data {
+        DataSource::File { path, .. } => get_data(path),
+        DataSource::Items(_) => unreachable!("the flags always name a data file"),
+    }
+    .unwrap_or_else(|err| exit(ErrorKind::ValueValidation, err.to_string()));
+    if let Err(err) = config.check(data.len()) {
+        exit(ErrorKind::ValueValidation, err.to_string());
+    }
+    let (mapa_height, mapa_width) = (config.mapa_height, config.mapa_width);
+    let max_iters = config.max_iters;
+    let params = AgentParams {
+        radius: config.agent_vision_radius,
+        k1: config.k1,
+        k2: config.k2,
+        alpha: config.alpha,
     };
-    let max_iters: usize = if args.len() > 2 {
-        args[2].parse::<usize>().unwrap_or(100_000)
-    } else {
-        100_000
+    println!(
+        "Config Radius {} Iters {} Seed {:?}",
+        params.radius, max_iters, config.seed
+    );
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
     };
-    println!("Config Radius {} Iters {}", radius, max_iters);
-    let mut mapa = init_objs();
-    let mut agents = create_agents(radius);
+    let mut mapa = init_objs(&data, mapa_height, mapa_width, &mut rng);
+    let mut agents = create_agents(params, config.qtd_agents, mapa_height, mapa_width, &mut rng);
     show_mapa(&mapa);
     for iter in 0..max_iters {
         if iter % 100000 == 0 {
@@ -22,7 +101,7 @@ fn main() {
             show_mapa(&mapa);
         }
         for agent in agents.iter_mut() {
-            agent.update_agent(&mut mapa);
+            agent.update_agent(&mut mapa, &mut rng);
         }
     }
     for agent in agents.iter_mut() {
@@ -43,7 +122,7 @@ fn main() {
             break;
         }
         for agent in remaining.iter_mut() {
-            agent.update_agent(&mut mapa);
+            agent.update_agent(&mut mapa, &mut rng);
         }
         if iter % 10000 == 0 {
             println!("Iteração Extra: {}", iter);
@@ -1,49 +1,58 @@
 use std::{sync::mpsc, thread::spawn};
 
-use agent::AgentStates;
-use graphics_engine::{App, EventsBridge};
+use agent::Grid;
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use cli::SimArgs;
+use graphics_engine::{App, EventsBridge, Layout};
+use map_render::{agent_color, group_color};
 use object::Object;
-use simulation::{Sim, SimConfig, SimState};
+use simulation::Sim;
+
+/// Ant clustering with a live view of the map
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    sim: SimArgs,
+}
 
 fn main() {
     // let title = "Ant Cluster";
-    let args: Vec<String> = std::env::args().collect();
-    let radius: usize = if args.len() > 1 {
-        args[1].parse::<usize>().unwrap_or(1)
-    } else {
-        1
-    };
-    let max_iters: usize = if args.len() > 2 {
-        args[2].parse::<usize>().unwrap_or(100_000)
-    } else {
-        100_000
-    };
-    println!("Config Radius {} Iters {}", radius, max_iters);
-    let config = SimConfig {
-        max_iters,
-        mapa_height: 100,
-        mapa_width: 100,
-        qtd_agents: 40,
-        agent_vision_radius: radius,
-    };
+    let cli = Cli::parse();
+    let config = cli
+        .sim
+        .to_config()
+        .unwrap_or_else(|err| Cli::command().error(ErrorKind::ValueValidation, err).exit());
+    println!(
+        "Config Radius {} Iters {} Seed {:?} K1 {} K2 {} Alpha {}",
+        config.agent_vision_radius,
+        config.max_iters,
+        config.seed,
+        config.k1,
+        config.k2,
+        config.alpha
+    );
     let (sender_signal, receiver_signal) = mpsc::channel::<bool>();
     let (sender_data, receiver_data) = mpsc::channel::<Vec<graphics_engine::Object>>();
 
+    let (mapa_height, mapa_width) = (config.mapa_height, config.mapa_width);
+    let layout = match config.grid {
+        Grid::Square => Layout::Square,
+        Grid::Hex => Layout::Hex,
+    };
+    let mut sim = Sim::try_create(config)
+        .unwrap_or_else(|err| Cli::command().error(ErrorKind::ValueValidation, err).exit());
+
     spawn(move || {
-        let mut sim = Sim::create(config);
         loop {
             sim.update();
 
             let signal = receiver_signal.try_recv();
-            match signal {
-                Ok(msg) => {
-                    if msg {
-                        sender_data.send(handle_update(&sim)).unwrap();
-                    } else {
-                        break;
-                    }
+            if let Ok(msg) = signal {
+                if msg {
+                    sender_data.send(handle_update(&sim)).unwrap();
+                } else {
+                    break;
                 }
-                Err(_) => {}
             }
             // if sim.get_state() == SimState::DONE{
             //     break;
@@ -62,13 +71,7 @@ fn main() {
         for i in 0..sim.mapa.len() {
             for j in 0..sim.mapa[i].len() {
                 if !sim.mapa[i][j].is_empty() {
-                    let color = match sim.mapa[i][j].group {
-                        1 => [0.6, 0.6, 0.6, 1.0],
-                        2 => [0.7, 0.6, 0.8, 1.0],
-                        3 => [0.1, 0.9, 0.6, 1.0],
-                        4 => [0.9, 0.5, 0.3, 1.0],
-                        _ => [1.0; 4],
-                    };
+                    let color = group_color(sim.mapa[i][j].label);
                     objects.push(graphics_engine::Object::create(j, i, color));
                 }
             }
@@ -78,18 +81,13 @@ fn main() {
             let pos = agent.get_pos();
             let y = pos.i;
             let x = pos.j;
-            let color = match agent.get_state() {
-                AgentStates::CARRYING => graphics_engine::BLUE,
-                AgentStates::SEARCHING => graphics_engine::GREEN,
-                AgentStates::FINISHING => graphics_engine::BLUE,
-                AgentStates::DONE => graphics_engine::BLACK,
-            };
+            let color = agent_color(agent.get_state());
             objects.push(graphics_engine::Object::create(x, y, color));
         }
 
-        return objects;
+        objects
     }
-    let mut app = App::create("Ant Cluster", config.mapa_height, config.mapa_width);
+    let mut app = App::create("Ant Cluster", mapa_height, mapa_width).with_layout(layout);
 
     let mut events = EventsBridge::create();
     while let Some(e) = events.next(&mut app.window_handle) {
@@ -103,7 +101,7 @@ fn main() {
             app.update(&args, objects);
         }
 
-        if let Some(_) = e.close_args() {
+        if e.close_args().is_some() {
             sender_signal.send(false).unwrap();
         }
     }
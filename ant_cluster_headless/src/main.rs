@@ -0,0 +1,287 @@
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use cli::{MetricArg, SimArgs};
+use map_render::{write_png, write_svg, Recorder, RenderOptions};
+use object::Object;
+use simulation::{stats::StatsCollector, EventFormat, EventWriter, ItemLocation, Sim, SimState};
+
+/// Creates `path`, and its directory if needed.
+fn create_file(path: &Path) -> io::Result<fs::File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::File::create(path)
+}
+
+fn write_map(path: &Path, sim: &Sim) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    for row in sim.mapa.iter() {
+        let cells = row
+            .iter()
+            .map(|cel| match (cel.is_empty(), cel.label) {
+                (true, _) => ".".to_string(),
+                (false, Some(label)) => label.to_string(),
+                (false, None) => "*".to_string(),
+            })
+            .collect::<Vec<String>>();
+        writeln!(file, "{}", cells.join(" "))?;
+    }
+    Ok(())
+}
+
+fn write_items(path: &Path, sim: &Sim) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    writeln!(file, "id,label,cluster,i,j,pick_ups,drops,distance")?;
+    let assignments = sim.cluster_assignments(1);
+    let locations = sim.item_locations();
+    let mut labels = vec![None; assignments.len()];
+    for cel in sim.mapa.iter().flatten() {
+        if !cel.is_empty() {
+            labels[cel.id] = cel.label;
+        }
+    }
+    for (id, stats) in sim.item_stats().iter().enumerate() {
+        let (i, j) = match locations[id] {
+            Some(ItemLocation::Ground(pos)) => (pos.i.to_string(), pos.j.to_string()),
+            _ => (String::new(), String::new()),
+        };
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},{}",
+            id,
+            labels[id].map_or(String::new(), |label| label.to_string()),
+            assignments[id].map_or(String::new(), |cluster| cluster.to_string()),
+            i,
+            j,
+            stats.pick_ups,
+            stats.drops,
+            stats.distance
+        )?;
+    }
+    Ok(())
+}
+
+fn write_metrics(path: &Path, sim: &Sim, elapsed: Duration) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    let metrics = sim.metrics();
+    writeln!(file, "metric,value")?;
+    writeln!(
+        file,
+        "seed,{}",
+        sim.config
+            .seed
+            .map_or(String::new(), |seed| seed.to_string())
+    )?;
+    writeln!(file, "iters,{}", sim.get_iter())?;
+    writeln!(file, "extra_iters,{}", sim.extra_iters)?;
+    writeln!(file, "elapsed_secs,{}", elapsed.as_secs_f64())?;
+    writeln!(file, "clusters,{}", metrics.clusters)?;
+    writeln!(file, "purity,{}", metrics.purity)?;
+    writeln!(file, "rand_index,{}", metrics.rand_index)?;
+    writeln!(file, "adjusted_rand_index,{}", metrics.adjusted_rand_index)?;
+    writeln!(file, "f_measure,{}", metrics.f_measure)?;
+    writeln!(file, "nmi,{}", metrics.nmi)?;
+    writeln!(file, "spatial_entropy,{}", metrics.spatial_entropy)?;
+    Ok(())
+}
+
+/// Runs the simulation to completion without a window and writes the final
+/// map, per-item results and clustering metrics
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    sim: SimArgs,
+    /// Directory the results are written to
+    #[arg(long, value_name = "DIR", default_value = "output")]
+    output: PathBuf,
+    /// Side of a map cell in the exported images, in pixels
+    #[arg(long, value_name = "PIXELS", default_value_t = 8)]
+    cell_size: usize,
+    /// Record the run as an animated GIF (when FILE ends in .gif) or as
+    /// numbered PNG frames in the FILE directory
+    #[arg(long, value_name = "FILE")]
+    record: Option<PathBuf>,
+    /// Updates between two recorded frames
+    #[arg(long, value_name = "N", default_value_t = 1_000, requires = "record")]
+    record_every: usize,
+    /// Display time of every GIF frame, in hundredths of a second
+    #[arg(long, value_name = "CS", default_value_t = 10, requires = "record")]
+    frame_delay: u16,
+    /// Log every pick-up and drop to this file, as CSV when FILE ends in
+    /// .csv and as JSON lines otherwise
+    #[arg(long, value_name = "FILE")]
+    events: Option<PathBuf>,
+    /// Write a time series of the simulation statistics to this CSV file
+    #[arg(long, value_name = "FILE")]
+    stats: Option<PathBuf>,
+    /// Updates between two statistics samples
+    #[arg(long, value_name = "N", default_value_t = 1_000, requires = "stats")]
+    stats_every: usize,
+    /// Quality metric included in the statistics
+    #[arg(long, value_enum, default_value_t = MetricArg::Purity, requires = "stats")]
+    stats_metric: MetricArg,
+    /// Periodically save the simulation state to this file
+    #[arg(long, value_name = "FILE")]
+    snapshot: Option<PathBuf>,
+    /// Iterations between two snapshots
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 10_000,
+        requires = "snapshot"
+    )]
+    snapshot_every: usize,
+    /// Check every agent move and stop at the first broken invariant
+    #[arg(long)]
+    paranoid: bool,
+    /// Updates between two checks that no item was lost or duplicated
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "paranoid")]
+    paranoid_every: usize,
+    /// Resume from a snapshot instead of starting a new run, with the
+    /// simulation settings saved in it
+    #[arg(long, value_name = "FILE", conflicts_with = "SimArgs")]
+    resume: Option<PathBuf>,
+}
+
+fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    let every = [
+        cli.snapshot_every,
+        cli.record_every,
+        cli.stats_every,
+        cli.paranoid_every,
+    ];
+    if every.contains(&0) {
+        Cli::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--snapshot-every, --record-every, --stats-every and --paranoid-every must be at least 1",
+            )
+            .exit();
+    }
+    let output = cli.output.as_path();
+
+    let start = Instant::now();
+    let mut sim = match &cli.resume {
+        Some(path) => {
+            let sim = Sim::load_snapshot(path).map_err(io::Error::other)?;
+            println!("Resuming {} at iter {}", path.display(), sim.get_iter());
+            sim
+        }
+        None => {
+            let mut config = cli
+                .sim
+                .to_config()
+                .unwrap_or_else(|err| Cli::command().error(ErrorKind::ValueValidation, err).exit());
+            config.seed = Some(config.seed.unwrap_or_else(rand::random));
+            Sim::try_create(config)
+                .unwrap_or_else(|err| Cli::command().error(ErrorKind::ValueValidation, err).exit())
+        }
+    };
+    let config = &sim.config;
+    println!(
+        "Config Radius {} Iters {} Agents {} Map {}x{} Seed {:?}",
+        config.agent_vision_radius,
+        config.max_iters,
+        config.qtd_agents,
+        config.mapa_height,
+        config.mapa_width,
+        config.seed
+    );
+    let frame_options = RenderOptions {
+        cell_size: cli.cell_size,
+        agents: true,
+    };
+    let mut recorder = match &cli.record {
+        Some(path) if path.extension().is_some_and(|ext| ext == "gif") => Some(
+            Recorder::gif(path, &sim, cli.record_every, frame_options, cli.frame_delay)
+                .map_err(io::Error::other)?,
+        ),
+        Some(path) => Some(
+            Recorder::png_frames(path, &sim, cli.record_every, frame_options)
+                .map_err(io::Error::other)?,
+        ),
+        None => None,
+    };
+    let mut events = match &cli.events {
+        Some(path) => {
+            let format = match path.extension().is_some_and(|ext| ext == "csv") {
+                true => EventFormat::Csv,
+                false => EventFormat::JsonLines,
+            };
+            sim.record_events(true);
+            Some(EventWriter::new(
+                BufWriter::new(create_file(path)?),
+                format,
+            )?)
+        }
+        None => None,
+    };
+    let mut stats = cli
+        .stats
+        .as_ref()
+        .map(|_| StatsCollector::new(cli.stats_every, cli.stats_metric.into()));
+    if cli.paranoid {
+        sim.check_items_every(Some(cli.paranoid_every));
+        sim.check_items().map_err(io::Error::other)?;
+    }
+    while sim.get_state() != SimState::DONE {
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(&sim).map_err(io::Error::other)?;
+        }
+        if let Some(stats) = stats.as_mut() {
+            stats.record(&sim);
+        }
+        if cli.paranoid {
+            sim.try_update().map_err(io::Error::other)?;
+        } else {
+            sim.update();
+        }
+        if let Some(events) = events.as_mut() {
+            for event in sim.take_events() {
+                events.write(&event)?;
+            }
+        }
+        if let Some(path) = &cli.snapshot {
+            if (sim.get_iter() + sim.extra_iters) % cli.snapshot_every == 0 {
+                sim.save_snapshot(path).map_err(io::Error::other)?;
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+    if let Some(events) = events {
+        events.into_inner().flush()?;
+    }
+    if let (Some(path), Some(mut stats)) = (&cli.stats, stats) {
+        stats.record_now(&sim);
+        stats.write_csv(BufWriter::new(create_file(path)?))?;
+    }
+    if let Some(mut recorder) = recorder {
+        recorder.capture(&sim).map_err(io::Error::other)?;
+        println!("Recorded {} frames", recorder.frames());
+    }
+
+    fs::create_dir_all(output)?;
+    write_map(&output.join("map.txt"), &sim)?;
+    write_items(&output.join("items.csv"), &sim)?;
+    write_metrics(&output.join("metrics.csv"), &sim, elapsed)?;
+    let options = RenderOptions {
+        cell_size: cli.cell_size,
+        agents: false,
+    };
+    write_png(&output.join("map.png"), &sim, options).map_err(io::Error::other)?;
+    write_svg(&output.join("map.svg"), &sim, options).map_err(io::Error::other)?;
+    println!(
+        "Done in {:.2}s ({} extra iters), results in {}",
+        elapsed.as_secs_f64(),
+        sim.extra_iters,
+        output.display()
+    );
+    Ok(())
+}
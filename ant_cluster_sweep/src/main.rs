@@ -0,0 +1,191 @@
+use std::{
+    fmt::Display,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread,
+};
+
+use agent::{DecisionModelKind, Grid, Topology};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use cli::SimArgs;
+use data_retrieve::Normalization;
+use map::DataSource;
+use object::DistanceMetric;
+use simulation::{
+    sweep::{run_sweep, SweepGrid, SweepRun},
+    SimConfig,
+};
+
+/// Runs a grid of simulations in parallel and writes one CSV row per run.
+/// The simulation flags set the base config; every `--sweep-*` list
+/// replaces the matching value with each of the listed ones.
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    sim: SimArgs,
+    /// Iteration budgets to sweep over, comma separated
+    #[arg(long, value_delimiter = ',', value_name = "LIST")]
+    sweep_iters: Vec<usize>,
+    /// Agent counts to sweep over, comma separated
+    #[arg(long, value_delimiter = ',', value_name = "LIST")]
+    sweep_agents: Vec<usize>,
+    /// Vision radii to sweep over, comma separated
+    #[arg(long, value_delimiter = ',', value_name = "LIST")]
+    sweep_radius: Vec<usize>,
+    /// Pick-up constants to sweep over, comma separated
+    #[arg(long, value_delimiter = ',', value_name = "LIST")]
+    sweep_k1: Vec<f64>,
+    /// Drop constants to sweep over, comma separated
+    #[arg(long, value_delimiter = ',', value_name = "LIST")]
+    sweep_k2: Vec<f64>,
+    /// Runs of every config, each with its own seed
+    #[arg(long, default_value_t = 1)]
+    repetitions: usize,
+    /// Worker threads [default: number of CPU cores]
+    #[arg(long)]
+    threads: Option<usize>,
+    /// CSV file the results are written to
+    #[arg(long, value_name = "FILE", default_value = "sweep.csv")]
+    output: PathBuf,
+}
+
+fn invalid(err: impl Display) -> ! {
+    Cli::command().error(ErrorKind::ValueValidation, err).exit()
+}
+
+/// Columns of the settings that are not numbers, named after the values of
+/// the matching flags: normalization, decision_model, sigmoid_slope,
+/// distance_metric, minkowski_p, topology, grid, data and labelled.
+fn settings(config: &SimConfig) -> [String; 9] {
+    let normalization = match config.normalization {
+        Normalization::None => "none",
+        Normalization::MinMax => "min-max",
+        Normalization::ZScore => "z-score",
+    };
+    let (decision_model, slope) = match &config.decision_model {
+        DecisionModelKind::Deneubourg => ("deneubourg", String::new()),
+        DecisionModelKind::Sigmoid { slope } => ("sigmoid", slope.to_string()),
+        DecisionModelKind::Threshold => ("threshold", String::new()),
+        DecisionModelKind::Custom(_) => ("custom", String::new()),
+    };
+    let (distance, p) = match &config.distance_metric {
+        DistanceMetric::Euclidean => ("euclidean", String::new()),
+        DistanceMetric::Manhattan => ("manhattan", String::new()),
+        DistanceMetric::Chebyshev => ("chebyshev", String::new()),
+        DistanceMetric::Cosine => ("cosine", String::new()),
+        DistanceMetric::Minkowski(p) => ("minkowski", p.to_string()),
+        DistanceMetric::Custom(_) => ("custom", String::new()),
+    };
+    let topology = match config.topology {
+        Topology::Torus => "torus",
+        Topology::Bounded => "bounded",
+        Topology::Reflective => "reflective",
+    };
+    let grid = match config.grid {
+        Grid::Square => "square",
+        Grid::Hex => "hex",
+    };
+    let (data, labelled) = match &config.data {
+        DataSource::File { path, labelled } => (path.clone(), labelled.to_string()),
+        DataSource::Items(_) => ("items".to_string(), String::new()),
+    };
+    [
+        normalization.to_string(),
+        decision_model.to_string(),
+        slope,
+        distance.to_string(),
+        p,
+        topology.to_string(),
+        grid.to_string(),
+        data,
+        labelled,
+    ]
+}
+
+fn write_runs(path: &Path, runs: &[SweepRun]) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    writeln!(
+        file,
+        "config,repetition,seed,max_iters,mapa_height,mapa_width,qtd_agents,\
+         agent_vision_radius,k1,k2,alpha,estimate_alpha,normalization,\
+         decision_model,sigmoid_slope,distance_metric,minkowski_p,topology,grid,\
+         data,labelled,clusters,purity,rand_index,adjusted_rand_index,f_measure,\
+         nmi,spatial_entropy,extra_iters,wall_secs"
+    )?;
+    for run in runs {
+        let config = &run.config;
+        let metrics = &run.metrics;
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            run.cell,
+            run.repetition,
+            config.seed.map_or(String::new(), |seed| seed.to_string()),
+            config.max_iters,
+            config.mapa_height,
+            config.mapa_width,
+            config.qtd_agents,
+            config.agent_vision_radius,
+            config.k1,
+            config.k2,
+            run.alpha,
+            config.estimate_alpha,
+            settings(config).join(","),
+            metrics.clusters,
+            metrics.purity,
+            metrics.rand_index,
+            metrics.adjusted_rand_index,
+            metrics.f_measure,
+            metrics.nmi,
+            metrics.spatial_entropy,
+            run.extra_iters,
+            run.wall_time.as_secs_f64()
+        )?;
+    }
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    let base = match cli.sim.to_config() {
+        Ok(config) => config,
+        Err(err) => invalid(err),
+    };
+    if cli.repetitions == 0 {
+        invalid("--repetitions must be at least 1");
+    }
+    let grid = SweepGrid {
+        max_iters: cli.sweep_iters,
+        qtd_agents: cli.sweep_agents,
+        agent_vision_radius: cli.sweep_radius,
+        k1: cli.sweep_k1,
+        k2: cli.sweep_k2,
+    };
+    let configs = grid.configs(&base);
+    let threads = cli
+        .threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |count| count.get()));
+    let base_seed = base.seed.unwrap_or_else(rand::random);
+    let total = configs.len() * cli.repetitions;
+    println!(
+        "Sweeping {} configs x {} repetitions on {} threads, base seed {}",
+        configs.len(),
+        cli.repetitions,
+        threads,
+        base_seed
+    );
+
+    let runs = run_sweep(&configs, cli.repetitions, base_seed, threads, |run| {
+        println!(
+            "config {} repetition {} done in {:.2}s",
+            run.cell,
+            run.repetition,
+            run.wall_time.as_secs_f64()
+        );
+    })
+    .unwrap_or_else(|err| invalid(err));
+    write_runs(&cli.output, &runs)?;
+    println!("{} runs written to {}", total, cli.output.display());
+    Ok(())
+}
@@ -0,0 +1,565 @@
+use std::{
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use agent::{
+    AgentConfig, DecisionModelKind, Grid, Topology, DEFAULT_ALPHA, DEFAULT_K1, DEFAULT_K2,
+};
+use clap::{parser::ValueSource, ArgMatches, Args, Command, ValueEnum};
+use data_retrieve::{Normalization, DATA_1_FP};
+use map::DataSource;
+use object::DistanceMetric;
+use serde::{de::DeserializeOwned, Deserialize};
+use simulation::{metrics::QualityMetric, SimConfig};
+
+pub const DEFAULT_MAX_ITERS: usize = 100_000;
+pub const DEFAULT_MAPA_HEIGHT: usize = 100;
+pub const DEFAULT_MAPA_WIDTH: usize = 100;
+pub const DEFAULT_QTD_AGENTS: usize = 40;
+pub const DEFAULT_VISION_RADIUS: usize = 1;
+pub const DEFAULT_SIGMOID_SLOPE: f64 = 10.0;
+pub const DEFAULT_MINKOWSKI_P: f64 = 2.0;
+
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NormalizationArg {
+    None,
+    MinMax,
+    ZScore,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DecisionModelArg {
+    Deneubourg,
+    Sigmoid,
+    Threshold,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DistanceArg {
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+    Cosine,
+    Minkowski,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TopologyArg {
+    Torus,
+    Bounded,
+    Reflective,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GridArg {
+    Square,
+    Hex,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MetricArg {
+    Clusters,
+    Purity,
+    RandIndex,
+    AdjustedRandIndex,
+    FMeasure,
+    Nmi,
+    SpatialEntropy,
+}
+
+impl From<MetricArg> for QualityMetric {
+    fn from(arg: MetricArg) -> Self {
+        match arg {
+            MetricArg::Clusters => QualityMetric::Clusters,
+            MetricArg::Purity => QualityMetric::Purity,
+            MetricArg::RandIndex => QualityMetric::RandIndex,
+            MetricArg::AdjustedRandIndex => QualityMetric::AdjustedRandIndex,
+            MetricArg::FMeasure => QualityMetric::FMeasure,
+            MetricArg::Nmi => QualityMetric::Nmi,
+            MetricArg::SpatialEntropy => QualityMetric::SpatialEntropy,
+        }
+    }
+}
+
+impl From<TopologyArg> for Topology {
+    fn from(arg: TopologyArg) -> Self {
+        match arg {
+            TopologyArg::Torus => Topology::Torus,
+            TopologyArg::Bounded => Topology::Bounded,
+            TopologyArg::Reflective => Topology::Reflective,
+        }
+    }
+}
+
+impl From<GridArg> for Grid {
+    fn from(arg: GridArg) -> Self {
+        match arg {
+            GridArg::Square => Grid::Square,
+            GridArg::Hex => Grid::Hex,
+        }
+    }
+}
+
+impl From<NormalizationArg> for Normalization {
+    fn from(arg: NormalizationArg) -> Self {
+        match arg {
+            NormalizationArg::None => Normalization::None,
+            NormalizationArg::MinMax => Normalization::MinMax,
+            NormalizationArg::ZScore => Normalization::ZScore,
+        }
+    }
+}
+
+/// Simulation flags shared by the binaries. Every flag overrides the value
+/// read from `--config`, which in turn overrides the built-in default.
+#[derive(Debug, Clone, Default, Args)]
+pub struct SimArgs {
+    /// Experiment file (.toml or .json) with the simulation settings
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Iterations before the agents start finishing [default: 100000]
+    #[arg(long)]
+    pub iters: Option<usize>,
+    /// Map height in cells [default: 100]
+    #[arg(long)]
+    pub height: Option<usize>,
+    /// Map width in cells [default: 100]
+    #[arg(long)]
+    pub width: Option<usize>,
+    /// Number of agents [default: 40]
+    #[arg(long)]
+    pub agents: Option<usize>,
+    /// Agent vision radius in cells [default: 1]
+    #[arg(long)]
+    pub radius: Option<usize>,
+    /// Seed of the simulation RNG [default: random]
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Pick-up constant [default: 0.35]
+    #[arg(long)]
+    pub k1: Option<f64>,
+    /// Drop constant [default: 0.35]
+    #[arg(long)]
+    pub k2: Option<f64>,
+    /// Dissimilarity scale [default: 6.0]
+    #[arg(long)]
+    pub alpha: Option<f64>,
+    /// Estimate alpha from the mean pairwise distance of the data, or not
+    /// with `=false`
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub estimate_alpha: Option<bool>,
+    /// Feature normalisation applied before clustering [default: none]
+    #[arg(long, value_enum)]
+    pub normalization: Option<NormalizationArg>,
+    /// Pick-up and drop probability model [default: deneubourg]
+    #[arg(long, value_enum)]
+    pub decision_model: Option<DecisionModelArg>,
+    /// Slope of the sigmoid decision model [default: 10.0]
+    #[arg(long)]
+    pub sigmoid_slope: Option<f64>,
+    /// Distance between items [default: euclidean]
+    #[arg(long, value_enum)]
+    pub distance: Option<DistanceArg>,
+    /// Order of the Minkowski distance [default: 2.0]
+    #[arg(long)]
+    pub minkowski_p: Option<f64>,
+    /// Behaviour of the map edges [default: torus]
+    #[arg(long, value_enum)]
+    pub topology: Option<TopologyArg>,
+    /// Shape of the map cells [default: square]
+    #[arg(long, value_enum)]
+    pub grid: Option<GridArg>,
+    /// Data file to cluster [default: data/data_1.txt]
+    #[arg(long, value_name = "FILE")]
+    pub data: Option<String>,
+    /// Read every column of the data file as a feature, or read the last
+    /// one as the label with `=false`
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub unlabelled: Option<bool>,
+}
+
+/// Experiment file layout. Top-level keys map onto `SimConfig` and the
+/// `[agent]` table onto `AgentConfig`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentFile {
+    pub max_iters: Option<usize>,
+    pub mapa_height: Option<usize>,
+    pub mapa_width: Option<usize>,
+    pub qtd_agents: Option<usize>,
+    pub seed: Option<u64>,
+    pub data: Option<String>,
+    pub labelled: Option<bool>,
+    pub normalization: Option<NormalizationArg>,
+    pub topology: Option<TopologyArg>,
+    pub grid: Option<GridArg>,
+    #[serde(default)]
+    pub agent: AgentSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentSection {
+    pub vision_radius: Option<usize>,
+    pub k1: Option<f64>,
+    pub k2: Option<f64>,
+    pub alpha: Option<f64>,
+    pub estimate_alpha: Option<bool>,
+    pub decision_model: Option<DecisionModelArg>,
+    pub sigmoid_slope: Option<f64>,
+    pub distance_metric: Option<DistanceArg>,
+    pub minkowski_p: Option<f64>,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, message: String },
+    UnknownFormat(PathBuf),
+    Invalid(String),
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Io { path, source } => {
+                write!(f, "unable to read {}: {}", path.display(), source)
+            }
+            CliError::Parse { path, message } => {
+                write!(f, "invalid experiment file {}: {}", path.display(), message)
+            }
+            CliError::UnknownFormat(path) => {
+                write!(
+                    f,
+                    "unknown experiment file format {}, expected .toml or .json",
+                    path.display()
+                )
+            }
+            CliError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl ExperimentFile {
+    pub fn from_toml(contents: &str) -> Result<Self, String> {
+        toml::from_str(contents).map_err(|err| err.to_string())
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self, String> {
+        serde_json::from_str(contents).map_err(|err| err.to_string())
+    }
+
+    /// Reads a TOML or JSON experiment file, chosen by its extension.
+    pub fn load(path: &Path) -> Result<Self, CliError> {
+        read(path)
+    }
+
+    /// Keys set in a TOML or JSON experiment file, with the keys of the
+    /// `[agent]` table as `agent.<key>`.
+    pub fn keys(path: &Path) -> Result<Vec<String>, CliError> {
+        let mut keys = vec![];
+        if let serde_json::Value::Object(table) = read(path)? {
+            for (key, value) in table {
+                match value {
+                    serde_json::Value::Object(section) => {
+                        keys.extend(section.keys().map(|name| format!("{}.{}", key, name)))
+                    }
+                    _ => keys.push(key),
+                }
+            }
+        }
+        Ok(keys)
+    }
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> Result<T, CliError> {
+    let contents = fs::read_to_string(path).map_err(|source| CliError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&contents).map_err(|err| err.to_string()),
+        Some("json") => serde_json::from_str(&contents).map_err(|err| err.to_string()),
+        _ => return Err(CliError::UnknownFormat(path.to_path_buf())),
+    };
+    parsed.map_err(|message| CliError::Parse {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+fn at_least_one(value: usize, flag: &str) -> Result<usize, CliError> {
+    if value == 0 {
+        return Err(CliError::Invalid(format!("--{} must be at least 1", flag)));
+    }
+    Ok(value)
+}
+
+impl SimArgs {
+    /// Flags given on the command line and keys set in `--config` that are
+    /// not in `flags` or `keys`, for binaries that only run part of the
+    /// simulation. `flags` are field names of `SimArgs`, `matches` the
+    /// matches these flags were parsed from.
+    pub fn unsupported(
+        &self,
+        matches: &ArgMatches,
+        flags: &[&str],
+        keys: &[&str],
+    ) -> Result<Vec<String>, CliError> {
+        let command = SimArgs::augment_args(Command::new("sim"));
+        let mut unsupported: Vec<String> = command
+            .get_arguments()
+            .filter(|arg| !flags.contains(&arg.get_id().as_str()))
+            .filter(|arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            })
+            .map(|arg| format!("--{}", arg.get_long().unwrap_or(arg.get_id().as_str())))
+            .collect();
+        if let Some(path) = &self.config {
+            let file_keys = ExperimentFile::keys(path)?;
+            unsupported.extend(
+                file_keys
+                    .into_iter()
+                    .filter(|key| !keys.contains(&key.as_str())),
+            );
+        }
+        Ok(unsupported)
+    }
+
+    /// Builds the simulation configuration from the flags and `--config`.
+    pub fn to_config(&self) -> Result<SimConfig, CliError> {
+        let file = match &self.config {
+            Some(path) => ExperimentFile::load(path)?,
+            None => ExperimentFile::default(),
+        };
+        self.to_config_with(file)
+    }
+
+    /// Builds the simulation configuration from the flags on top of `file`.
+    pub fn to_config_with(&self, file: ExperimentFile) -> Result<SimConfig, CliError> {
+        let agent = file.agent;
+        let max_iters = self.iters.or(file.max_iters).unwrap_or(DEFAULT_MAX_ITERS);
+        let mapa_height = self
+            .height
+            .or(file.mapa_height)
+            .unwrap_or(DEFAULT_MAPA_HEIGHT);
+        let mapa_width = self.width.or(file.mapa_width).unwrap_or(DEFAULT_MAPA_WIDTH);
+        let qtd_agents = self
+            .agents
+            .or(file.qtd_agents)
+            .unwrap_or(DEFAULT_QTD_AGENTS);
+        let radius = self
+            .radius
+            .or(agent.vision_radius)
+            .unwrap_or(DEFAULT_VISION_RADIUS);
+        let k1 = self.k1.or(agent.k1).unwrap_or(DEFAULT_K1);
+        let k2 = self.k2.or(agent.k2).unwrap_or(DEFAULT_K2);
+        let alpha = self.alpha.or(agent.alpha).unwrap_or(DEFAULT_ALPHA);
+
+        let decision_model = match self
+            .decision_model
+            .or(agent.decision_model)
+            .unwrap_or(DecisionModelArg::Deneubourg)
+        {
+            DecisionModelArg::Deneubourg => DecisionModelKind::Deneubourg,
+            DecisionModelArg::Sigmoid => {
+                let slope = self
+                    .sigmoid_slope
+                    .or(agent.sigmoid_slope)
+                    .unwrap_or(DEFAULT_SIGMOID_SLOPE);
+                DecisionModelKind::Sigmoid { slope }
+            }
+            DecisionModelArg::Threshold => DecisionModelKind::Threshold,
+        };
+        let distance_metric = match self
+            .distance
+            .or(agent.distance_metric)
+            .unwrap_or(DistanceArg::Euclidean)
+        {
+            DistanceArg::Euclidean => DistanceMetric::Euclidean,
+            DistanceArg::Manhattan => DistanceMetric::Manhattan,
+            DistanceArg::Chebyshev => DistanceMetric::Chebyshev,
+            DistanceArg::Cosine => DistanceMetric::Cosine,
+            DistanceArg::Minkowski => DistanceMetric::Minkowski(
+                self.minkowski_p
+                    .or(agent.minkowski_p)
+                    .unwrap_or(DEFAULT_MINKOWSKI_P),
+            ),
+        };
+
+        AgentConfig::new(radius, mapa_height, mapa_width, k1, k2, alpha)
+            .and_then(|config| config.with_distance_metric(distance_metric.clone()))
+            .and_then(|config| config.with_decision_model(decision_model.clone()))
+            .map_err(|err| CliError::Invalid(err.to_string()))?;
+
+        Ok(SimConfig {
+            max_iters: at_least_one(max_iters, "iters")?,
+            mapa_height: at_least_one(mapa_height, "height")?,
+            mapa_width: at_least_one(mapa_width, "width")?,
+            qtd_agents: at_least_one(qtd_agents, "agents")?,
+            agent_vision_radius: at_least_one(radius, "radius")?,
+            seed: self.seed.or(file.seed),
+            k1,
+            k2,
+            alpha,
+            estimate_alpha: self
+                .estimate_alpha
+                .or(agent.estimate_alpha)
+                .unwrap_or(false),
+            normalization: self
+                .normalization
+                .or(file.normalization)
+                .map_or(Normalization::None, Normalization::from),
+            decision_model,
+            distance_metric,
+            topology: self
+                .topology
+                .or(file.topology)
+                .map_or(Topology::Torus, Topology::from),
+            grid: self.grid.or(file.grid).map_or(Grid::Square, Grid::from),
+            data: DataSource::File {
+                path: self
+                    .data
+                    .clone()
+                    .or(file.data)
+                    .unwrap_or(DATA_1_FP.to_string()),
+                labelled: self
+                    .unlabelled
+                    .map(|unlabelled| !unlabelled)
+                    .or(file.labelled)
+                    .unwrap_or(true),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches, Parser};
+
+    #[derive(Parser)]
+    struct TestArgs {
+        #[command(flatten)]
+        sim: SimArgs,
+    }
+
+    fn parse(args: &[&str]) -> SimArgs {
+        TestArgs::try_parse_from([&["test"], args].concat())
+            .unwrap()
+            .sim
+    }
+
+    #[test]
+    fn flags_override_file() {
+        let file = ExperimentFile::from_toml(
+            r#"
+            max_iters = 500
+            mapa_height = 30
+            seed = 9
+            topology = "bounded"
+
+            [agent]
+            vision_radius = 2
+            k1 = 0.1
+            decision_model = "threshold"
+            "#,
+        )
+        .unwrap();
+        let config = parse(&["--height", "50", "--k1", "0.2", "--grid", "hex"])
+            .to_config_with(file)
+            .unwrap();
+        assert_eq!(config.max_iters, 500);
+        assert_eq!(config.mapa_height, 50);
+        assert_eq!(config.mapa_width, DEFAULT_MAPA_WIDTH);
+        assert_eq!(config.agent_vision_radius, 2);
+        assert_eq!(config.seed, Some(9));
+        assert_eq!(config.topology, Topology::Bounded);
+        assert_eq!(config.grid, Grid::Hex);
+        assert_eq!(config.k1, 0.2);
+        assert!(matches!(
+            config.decision_model,
+            DecisionModelKind::Threshold
+        ));
+
+        let file = ExperimentFile::from_json(r#"{"qtd_agents": 7, "agent": {"k2": 0.5}}"#).unwrap();
+        let config = parse(&[]).to_config_with(file).unwrap();
+        assert_eq!(config.qtd_agents, 7);
+        assert_eq!(config.k2, 0.5);
+
+        let file = || {
+            ExperimentFile::from_toml("labelled = false\n[agent]\nestimate_alpha = true").unwrap()
+        };
+        let labelled = |config: &SimConfig| matches!(config.data, DataSource::File { labelled, .. } if labelled);
+        let config = parse(&[]).to_config_with(file()).unwrap();
+        assert!(config.estimate_alpha);
+        assert!(!labelled(&config));
+        let config = parse(&["--estimate-alpha=false", "--unlabelled=false"])
+            .to_config_with(file())
+            .unwrap();
+        assert!(!config.estimate_alpha);
+        assert!(labelled(&config));
+        let config = parse(&["--estimate-alpha", "--unlabelled"])
+            .to_config_with(ExperimentFile::default())
+            .unwrap();
+        assert!(config.estimate_alpha);
+        assert!(!labelled(&config));
+    }
+
+    #[test]
+    fn lists_unsupported_settings() {
+        let path = std::env::temp_dir().join(format!("experiment-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "max_iters = 5\nnormalization = \"min-max\"\n[agent]\nvision_radius = 2\nk1 = 0.1\n",
+        )
+        .unwrap();
+        let config = format!("--config={}", path.display());
+        let args = [
+            "test",
+            &config,
+            "--iters",
+            "9",
+            "--k2",
+            "0.5",
+            "--estimate-alpha",
+        ];
+        let matches = TestArgs::command().get_matches_from(args);
+        let sim = TestArgs::from_arg_matches(&matches).unwrap().sim;
+        let mut unsupported = sim
+            .unsupported(
+                &matches,
+                &["config", "iters"],
+                &["max_iters", "agent.vision_radius"],
+            )
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        unsupported.sort();
+        assert_eq!(
+            unsupported,
+            ["--estimate-alpha", "--k2", "agent.k1", "normalization"]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert!(parse(&["--k1=-1"])
+            .to_config_with(ExperimentFile::default())
+            .is_err());
+        assert!(parse(&["--agents", "0"])
+            .to_config_with(ExperimentFile::default())
+            .is_err());
+        assert!(ExperimentFile::from_toml("unknown = 1").is_err());
+        assert!(TestArgs::try_parse_from(["test", "--radius", "abc"]).is_err());
+    }
+}
@@ -1,10 +1,17 @@
-use std::fs;
+use std::{fmt::Display, fs, io};
 
 pub const DATA_1_FP: &str = "data/data_1.txt";
 
-use object::Object;
+use object::{DistanceMetric, Object};
 
-#[derive(Copy, Clone)]
+mod normalize;
+mod sample;
+
+pub use normalize::{estimate_alpha, normalize, Normalization};
+pub use sample::{get_samples, parse_samples, Sample};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Data {
     pub x: f64,
     pub y: f64,
@@ -23,28 +30,151 @@ impl Object for Data {
         self.group == 0
     }
 
-    fn get_distance(&self, other_obj: &Data) -> f64 {
-        let diff_x = self.x - other_obj.x;
-        let diff_y = self.y - other_obj.y;
-        let square = (diff_x * diff_x) + (diff_y * diff_y);
-        square.sqrt()
+    fn get_distance(&self, other_obj: &Data, metric: &DistanceMetric) -> f64 {
+        metric.distance(&[self.x, self.y], &[other_obj.x, other_obj.y])
+    }
+}
+
+/// Error raised while reading a data file. `line` and `column` are 1-based.
+#[derive(Debug)]
+pub enum DataError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    MissingField {
+        line: usize,
+        column: usize,
+        field: &'static str,
+    },
+    InvalidNumber {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+    EmptyGroup {
+        line: usize,
+        column: usize,
+    },
+    InconsistentWidth {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for DataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataError::Io { path, source } => {
+                write!(f, "unable to read {}: {}", path, source)
+            }
+            DataError::MissingField {
+                line,
+                column,
+                field,
+            } => {
+                write!(f, "{}:{}: missing field `{}`", line, column, field)
+            }
+            DataError::InvalidNumber {
+                line,
+                column,
+                value,
+            } => {
+                write!(f, "{}:{}: invalid number `{}`", line, column, value)
+            }
+            DataError::EmptyGroup { line, column } => {
+                write!(
+                    f,
+                    "{}:{}: group 0 is reserved for empty cells",
+                    line, column
+                )
+            }
+            DataError::InconsistentWidth {
+                line,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "{}: expected {} columns, found {}",
+                    line, expected, found
+                )
+            }
+        }
     }
 }
 
-pub fn get_data(fp: &str) -> Vec<Data> {
+impl std::error::Error for DataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DataError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn parse_field<F: std::str::FromStr>(
+    fields: &[(usize, &str)],
+    index: usize,
+    field: &'static str,
+    line: usize,
+    line_len: usize,
+) -> Result<(usize, F), DataError> {
+    let (column, value) = match fields.get(index) {
+        Some(&(column, value)) => (column, value),
+        None => {
+            return Err(DataError::MissingField {
+                line,
+                column: line_len + 1,
+                field,
+            })
+        }
+    };
+    match value.parse() {
+        Ok(parsed) => Ok((column, parsed)),
+        Err(_) => Err(DataError::InvalidNumber {
+            line,
+            column,
+            value: value.to_string(),
+        }),
+    }
+}
+
+/// Parses `x y group` rows separated by whitespace, skipping blank lines and
+/// lines starting with `#`.
+pub fn parse_data(contents: &str) -> Result<Vec<Data>, DataError> {
     let mut data = vec![];
-    let fd = fs::read_to_string(fp).expect("To be able to open the file");
-    let lines = fd.lines();
-    for line in lines {
-        let items = line.split_whitespace().collect::<Vec<&str>>();
-        // println!("{}", line.to_string());
-        let x: f64 = items[0].parse().unwrap();
-        let y: f64 = items[1].parse().unwrap();
-        let group: u8 = items[2].parse().unwrap();
-        // println!("|{}\t||{}\t||{}|", x, y, group);
+    for (index, line) in contents.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let line_number = index + 1;
+        let fields = line
+            .split_whitespace()
+            .map(|item| (item.as_ptr() as usize - line.as_ptr() as usize + 1, item))
+            .collect::<Vec<(usize, &str)>>();
+        let (_, x) = parse_field(&fields, 0, "x", line_number, line.len())?;
+        let (_, y) = parse_field(&fields, 1, "y", line_number, line.len())?;
+        let (column, group) = parse_field(&fields, 2, "group", line_number, line.len())?;
+        if group == 0 {
+            return Err(DataError::EmptyGroup {
+                line: line_number,
+                column,
+            });
+        }
         data.push(Data { x, y, group });
     }
-    return data;
+    Ok(data)
+}
+
+pub fn get_data(fp: &str) -> Result<Vec<Data>, DataError> {
+    let fd = fs::read_to_string(fp).map_err(|source| DataError::Io {
+        path: fp.to_string(),
+        source,
+    })?;
+    parse_data(&fd)
 }
 
 #[cfg(test)]
@@ -53,7 +183,7 @@ mod tests {
 
     #[test]
     fn it_works() {
-        let data = get_data("../data/data_1.txt");
+        let data = get_data("../data/data_1.txt").unwrap();
         assert!(data.len() == 400);
         for d in data {
             assert_ne!(d.x, 0.0);
@@ -61,4 +191,49 @@ mod tests {
             assert_ne!(d.group, 0);
         }
     }
+
+    #[test]
+    fn loads_raw_files() {
+        assert_eq!(get_data("../data/raw_1.txt").unwrap().len(), 400);
+        assert_eq!(get_data("../data/raw_2.txt").unwrap().len(), 600);
+    }
+
+    #[test]
+    fn reports_position() {
+        let err = parse_data("# header\n1.0 2.0 1\n1.0 abc 2\n").unwrap_err();
+        assert!(matches!(
+            err,
+            DataError::InvalidNumber {
+                line: 3,
+                column: 5,
+                ..
+            }
+        ));
+        let err = parse_data("1.0 2.0\n").unwrap_err();
+        assert!(matches!(
+            err,
+            DataError::MissingField {
+                line: 1,
+                field: "group",
+                ..
+            }
+        ));
+        let err = parse_data("1.0 2.0 0\n").unwrap_err();
+        assert!(matches!(err, DataError::EmptyGroup { line: 1, column: 9 }));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let data = Data {
+            x: 1.5,
+            y: -2.0,
+            group: 3,
+        };
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<Data>(&json).unwrap(), data);
+        let sample = Sample::from(data);
+        let json = serde_json::to_string(&sample).unwrap();
+        assert_eq!(serde_json::from_str::<Sample>(&json).unwrap(), sample);
+    }
 }
@@ -0,0 +1,119 @@
+use object::{DistanceMetric, Object};
+
+use crate::Sample;
+
+/// Per-feature rescaling applied to the samples before they are placed on the map.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Normalization {
+    #[default]
+    None,
+    /// Rescales every feature to `[0, 1]`.
+    MinMax,
+    /// Rescales every feature to zero mean and unit standard deviation.
+    ZScore,
+}
+
+fn feature_count(samples: &[Sample]) -> usize {
+    samples
+        .iter()
+        .map(|sample| sample.features.len())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Normalises the features of `samples` in place. Constant features become 0.
+pub fn normalize(samples: &mut [Sample], normalization: Normalization) {
+    for feature in 0..feature_count(samples) {
+        let values = samples
+            .iter()
+            .filter_map(|sample| sample.features.get(feature).copied())
+            .collect::<Vec<f64>>();
+        let (offset, scale) = match normalization {
+            Normalization::None => return,
+            Normalization::MinMax => {
+                let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                (min, max - min)
+            }
+            Normalization::ZScore => {
+                let count = values.len() as f64;
+                let mean = values.iter().sum::<f64>() / count;
+                let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / count;
+                (mean, variance.sqrt())
+            }
+        };
+        for sample in samples.iter_mut() {
+            if let Some(value) = sample.features.get_mut(feature) {
+                *value = if scale > 0.0 {
+                    (*value - offset) / scale
+                } else {
+                    0.0
+                };
+            }
+        }
+    }
+}
+
+/// Estimates the dissimilarity scale `alpha` as the mean distance between
+/// every pair of non-empty samples. Returns 0 when there are fewer than two.
+pub fn estimate_alpha(samples: &[Sample], metric: &DistanceMetric) -> f64 {
+    let items = samples
+        .iter()
+        .filter(|sample| !sample.is_empty())
+        .collect::<Vec<&Sample>>();
+    let mut total = 0.0;
+    let mut pairs = 0usize;
+    for (index, sample) in items.iter().enumerate() {
+        for other in items[index + 1..].iter() {
+            total += sample.get_distance(other, metric);
+            pairs += 1;
+        }
+    }
+    if pairs == 0 {
+        return 0.0;
+    }
+    total / pairs as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> Vec<Sample> {
+        [[0.0, 5.0], [5.0, 5.0], [10.0, 5.0]]
+            .iter()
+            .enumerate()
+            .map(|(id, features)| Sample {
+                id,
+                features: features.to_vec(),
+                label: Some(1),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scales_features() {
+        let mut min_max = samples();
+        normalize(&mut min_max, Normalization::MinMax);
+        assert_eq!(min_max[0].features, vec![0.0, 0.0]);
+        assert_eq!(min_max[1].features, vec![0.5, 0.0]);
+        assert_eq!(min_max[2].features, vec![1.0, 0.0]);
+
+        let mut z_score = samples();
+        normalize(&mut z_score, Normalization::ZScore);
+        let scale = (50.0f64 / 3.0).sqrt();
+        assert!((z_score[0].features[0] + 5.0 / scale).abs() < 1e-12);
+        assert_eq!(z_score[1].features[0], 0.0);
+
+        let mut untouched = samples();
+        normalize(&mut untouched, Normalization::None);
+        assert_eq!(untouched, samples());
+    }
+
+    #[test]
+    fn mean_pairwise_distance() {
+        let alpha = estimate_alpha(&samples(), &DistanceMetric::Euclidean);
+        assert!((alpha - 20.0 / 3.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,140 @@
+use std::fs;
+
+use object::{DistanceMetric, Object};
+
+use crate::{parse_field, Data, DataError};
+
+/// Data item with an arbitrary number of features and an optional label.
+/// A sample without features marks an empty cell. `id` is the index of the
+/// sample in the data it was read from.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sample {
+    pub id: usize,
+    pub features: Vec<f64>,
+    pub label: Option<u8>,
+}
+
+impl Object for Sample {
+    fn clone_empty() -> Sample {
+        Sample::default()
+    }
+    fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    fn get_distance(&self, other_obj: &Sample, metric: &DistanceMetric) -> f64 {
+        metric.distance(&self.features, &other_obj.features)
+    }
+}
+
+impl From<Data> for Sample {
+    fn from(data: Data) -> Self {
+        Sample {
+            id: 0,
+            features: vec![data.x, data.y],
+            label: Some(data.group),
+        }
+    }
+}
+
+/// Parses rows of features separated by whitespace and/or commas, skipping
+/// blank lines and lines starting with `#`. When `labelled` is set the last
+/// column of each row is read as the label. Every row must have the same width.
+pub fn parse_samples(contents: &str, labelled: bool) -> Result<Vec<Sample>, DataError> {
+    let mut samples = vec![];
+    let mut width = None;
+    for (index, line) in contents.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let line_number = index + 1;
+        let mut fields = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|item| !item.is_empty())
+            .map(|item| (item.as_ptr() as usize - line.as_ptr() as usize + 1, item))
+            .collect::<Vec<(usize, &str)>>();
+        match width {
+            None => width = Some(fields.len()),
+            Some(expected) if expected != fields.len() => {
+                return Err(DataError::InconsistentWidth {
+                    line: line_number,
+                    expected,
+                    found: fields.len(),
+                });
+            }
+            Some(_) => {}
+        }
+        let label = if labelled {
+            let label_index = fields.len().saturating_sub(1);
+            let (_, label) = parse_field(&fields, label_index, "label", line_number, line.len())?;
+            fields.pop();
+            Some(label)
+        } else {
+            None
+        };
+        if fields.is_empty() {
+            return Err(DataError::MissingField {
+                line: line_number,
+                column: 1,
+                field: "feature",
+            });
+        }
+        let mut features = vec![];
+        for index in 0..fields.len() {
+            let (_, feature) = parse_field(&fields, index, "feature", line_number, line.len())?;
+            features.push(feature);
+        }
+        samples.push(Sample {
+            id: samples.len(),
+            features,
+            label,
+        });
+    }
+    Ok(samples)
+}
+
+pub fn get_samples(fp: &str, labelled: bool) -> Result<Vec<Sample>, DataError> {
+    let fd = fs::read_to_string(fp).map_err(|source| DataError::Io {
+        path: fp.to_string(),
+        source,
+    })?;
+    parse_samples(&fd, labelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_data;
+
+    #[test]
+    fn matches_data() {
+        let data = get_data("../data/data_2.txt").unwrap();
+        let samples = get_samples("../data/raw_2.txt", true).unwrap();
+        assert_eq!(samples.len(), data.len());
+        for (id, (sample, data)) in samples.iter().zip(data).enumerate() {
+            assert_eq!(sample.id, id);
+            assert_eq!(sample.features, Sample::from(data).features);
+            assert_eq!(sample.label, Some(data.group));
+        }
+    }
+
+    #[test]
+    fn parses_any_width() {
+        let samples = parse_samples("1,2,3,4\n5, 6, 7, 8\n", false).unwrap();
+        assert_eq!(samples[1].features, vec![5.0, 6.0, 7.0, 8.0]);
+        assert_eq!(samples[1].label, None);
+        let samples = parse_samples("1 2 3 4 2\n", true).unwrap();
+        assert_eq!(samples[0].features.len(), 4);
+        assert_eq!(samples[0].label, Some(2));
+        assert!(matches!(
+            parse_samples("1 2 3\n1 2\n", false),
+            Err(DataError::InconsistentWidth {
+                line: 2,
+                expected: 3,
+                found: 2
+            })
+        ));
+    }
+}
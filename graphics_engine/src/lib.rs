@@ -14,9 +14,19 @@ pub struct App {
     pub window_handle: Window,
     scene_height: usize,
     scene_width: usize,
+    layout: Layout,
     state: Vec<Object>,
 }
 
+/// Shape of the scene cells.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Layout {
+    #[default]
+    Square,
+    /// Pointy-top hexagons, with the odd rows shifted half a cell right.
+    Hex,
+}
+
 pub struct Object {
     pos: (usize, usize),
     color: [f32; 4],
@@ -56,10 +66,7 @@ impl EventsBridge {
     }
     pub fn next(&mut self, window: &mut Window) -> Option<EventBridge> {
         let event = self.events.next(window);
-        return match event {
-            Some(e) => Some(EventBridge::create(e)),
-            None => None,
-        };
+        event.map(EventBridge::create)
     }
 }
 
@@ -91,10 +98,16 @@ impl App {
             window_handle: window,
             scene_height,
             scene_width,
+            layout: Layout::default(),
             state: empty_state,
         }
     }
 
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
     pub fn render(&mut self, args: &RenderArgs) {
         let (win_width, win_height) = (args.window_size[0], args.window_size[1]);
         let scene_height = self.scene_height + 1;
@@ -107,6 +120,7 @@ impl App {
         // );
 
         let objects = self.state.iter();
+        let layout = self.layout;
 
         self.gl.draw(args.viewport(), |c, gl| {
             // Clear the screen.
@@ -117,10 +131,30 @@ impl App {
             for object in objects {
                 let (i, j) = object.pos;
                 let color = object.color;
-                let x = rect_width * i as f64;
                 let y = rect_height * j as f64;
-                let rectangle: graphics::types::Rectangle = [x, y, rect_width, rect_height];
-                graphics::rectangle(color, rectangle, t, gl);
+                match layout {
+                    Layout::Square => {
+                        let x = rect_width * i as f64;
+                        let rectangle: graphics::types::Rectangle =
+                            [x, y, rect_width, rect_height];
+                        graphics::rectangle(color, rectangle, t, gl);
+                    }
+                    Layout::Hex => {
+                        // Hexagons are a third taller than the row pitch so
+                        // that consecutive rows interlock.
+                        let x = rect_width * (i as f64 + 0.5 * (j % 2) as f64);
+                        let (w, h) = (rect_width, rect_height * 4.0 / 3.0);
+                        let hexagon = [
+                            [x + w / 2.0, y],
+                            [x + w, y + h / 4.0],
+                            [x + w, y + h * 3.0 / 4.0],
+                            [x + w / 2.0, y + h],
+                            [x, y + h * 3.0 / 4.0],
+                            [x, y + h / 4.0],
+                        ];
+                        graphics::polygon(color, &hexagon, t, gl);
+                    }
+                }
             }
         });
     }
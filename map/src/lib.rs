@@ -1,56 +1,136 @@
-use data_retrieve::{get_data, Data, DATA_1_FP};
+use data_retrieve::{get_samples, DataError, Sample, DATA_1_FP};
 use object::Object;
 use rand::Rng;
+use std::fmt::Display;
 
-pub type CarryValueType = Data;
+pub type CarryValueType = Sample;
 pub type MapaDef = Vec<Vec<CarryValueType>>;
 
+/// Where the items placed on the map come from. When `labelled` is set the
+/// last column of the file is read as the item label.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DataSource {
+    File { path: String, labelled: bool },
+    Items(Vec<Sample>),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MapError {
+    NotEnoughCells { cells: usize, items: usize },
+}
+
+impl Default for DataSource {
+    fn default() -> Self {
+        DataSource::File {
+            path: DATA_1_FP.to_string(),
+            labelled: true,
+        }
+    }
+}
+
+impl DataSource {
+    pub fn load(&self) -> Result<Vec<Sample>, DataError> {
+        match self {
+            DataSource::File { path, labelled } => get_samples(path, *labelled),
+            DataSource::Items(items) => Ok(items.clone()),
+        }
+    }
+}
+
+impl Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapError::NotEnoughCells { cells, items } => {
+                write!(f, "map has {} cells but {} items to place", cells, items)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
 fn init_map(mapa_height: usize, mapa_width: usize) -> MapaDef {
     let mut mapa = vec![];
     for i in 0..mapa_height {
         mapa.push(vec![]);
         for _ in 0..mapa_width {
-            mapa[i].push(Data::clone_empty());
+            mapa[i].push(Sample::clone_empty());
         }
     }
-    return mapa;
+    mapa
 }
 
-pub fn init_objs(mapa_height: usize, mapa_width: usize) -> MapaDef {
+pub fn init_objs<R: Rng>(
+    mapa_height: usize,
+    mapa_width: usize,
+    data: &[Sample],
+    rng: &mut R,
+) -> Result<MapaDef, MapError> {
+    let cells = mapa_height * mapa_width;
+    if cells < data.len() {
+        return Err(MapError::NotEnoughCells {
+            cells,
+            items: data.len(),
+        });
+    }
     let mut mapa = init_map(mapa_height, mapa_width);
-    let mut rng = rand::thread_rng();
     let mut qtd_done = 0;
-    let data = get_data(DATA_1_FP);
-    assert_eq!(data.len(), 400);
     while qtd_done < data.len() {
         let i: usize = rng.gen_range(0..mapa_height);
         let j: usize = rng.gen_range(0..mapa_width);
         // let value: u32 = rng.gen_range(1u32..=9u32);
-        let mapa_pos = mapa[i][j];
-        if !mapa_pos.is_empty() {
+        if !mapa[i][j].is_empty() {
             continue;
         }
-        mapa[i][j].x = data[qtd_done].x;
-        mapa[i][j].y = data[qtd_done].y;
-        mapa[i][j].group = data[qtd_done].group;
+        mapa[i][j] = data[qtd_done].clone();
         qtd_done += 1;
     }
-    return mapa;
+    Ok(mapa)
 }
 
 pub fn show_mapa(mapa: &MapaDef, mapa_width: usize) {
     let divisor = "-".repeat(mapa_width * 4 + 1);
     println!("{}", divisor);
-    for row in 0..mapa.len() {
-        for cel in &mapa[row] {
+    for row in mapa {
+        for cel in row {
             if cel.is_empty() {
                 print!("|   ");
                 continue;
             }
-            print!("| {} ", cel.group);
+            match cel.label {
+                Some(label) => print!("| {} ", label),
+                None => print!("| * "),
+            }
         }
-        print!("|\n");
+        println!("|");
     }
     println!("{}", divisor);
     println!();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn rejects_small_map() {
+        let data = vec![
+            Sample {
+                id: 0,
+                features: vec![1.0, 1.0],
+                label: Some(1),
+            };
+            5
+        ];
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(
+            init_objs(2, 2, &data, &mut rng).err(),
+            Some(MapError::NotEnoughCells { cells: 4, items: 5 })
+        );
+        let mapa = init_objs(2, 3, &data, &mut rng).unwrap();
+        let placed = mapa.iter().flatten().filter(|cel| !cel.is_empty()).count();
+        assert_eq!(placed, 5);
+    }
+}
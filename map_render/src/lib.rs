@@ -0,0 +1,386 @@
+use std::{
+    fmt::{Display, Write as _},
+    fs,
+    io::{self, BufWriter},
+    path::Path,
+};
+
+use agent::{AgentStates, Grid};
+use object::Object;
+use simulation::Sim;
+
+mod record;
+
+pub use record::Recorder;
+
+/// RGBA colour with components in `[0, 1]`, as used by `graphics_engine`.
+pub type Color = [f32; 4];
+
+pub const BACKGROUND: Color = [0.0, 0.0, 0.0, 1.0];
+pub const GREEN: Color = [0.0, 1.0, 0.0, 1.0];
+pub const BLUE: Color = [0.0, 0.0, 1.0, 1.0];
+pub const BLACK: Color = [0.0, 0.0, 0.0, 1.0];
+
+/// Most colours a gif palette holds.
+pub(crate) const GIF_COLORS: usize = 256;
+
+/// Colour of an item of the given group. Groups 1 to 4 keep their original
+/// colours, every other group gets its own hue and unlabelled items are
+/// drawn white.
+pub fn group_color(label: Option<u8>) -> Color {
+    match label {
+        None => [1.0; 4],
+        Some(1) => [0.6, 0.6, 0.6, 1.0],
+        Some(2) => [0.7, 0.6, 0.8, 1.0],
+        Some(3) => [0.1, 0.9, 0.6, 1.0],
+        Some(4) => [0.9, 0.5, 0.3, 1.0],
+        // Golden ratio steps keep consecutive hues far apart for any number
+        // of groups.
+        Some(label) => hue_color((label as f32 * 0.618_034).fract()),
+    }
+}
+
+/// Bright colour of the given hue in `[0, 1)`.
+fn hue_color(hue: f32) -> Color {
+    let (saturation, value) = (0.7, 0.95);
+    let sector = hue * 6.0;
+    let rising = value * (1.0 - saturation * (1.0 - sector.fract()));
+    let falling = value * (1.0 - saturation * sector.fract());
+    let low = value * (1.0 - saturation);
+    let [r, g, b] = match sector as usize {
+        0 => [value, rising, low],
+        1 => [falling, value, low],
+        2 => [low, value, rising],
+        3 => [low, falling, value],
+        4 => [rising, low, value],
+        _ => [value, low, falling],
+    };
+    [r, g, b, 1.0]
+}
+
+pub fn agent_color(state: AgentStates) -> Color {
+    match state {
+        AgentStates::CARRYING => BLUE,
+        AgentStates::SEARCHING => GREEN,
+        AgentStates::FINISHING => BLUE,
+        AgentStates::DONE => BLACK,
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RenderOptions {
+    /// Side of every map cell, in pixels.
+    pub cell_size: usize,
+    /// Draws the agents on top of the items.
+    pub agents: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            cell_size: 8,
+            agents: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    Png(png::EncodingError),
+    Gif(gif::EncodingError),
+    TooLarge {
+        width: usize,
+        height: usize,
+    },
+    /// A recorded simulation changed its map size between two frames.
+    SizeMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// The items and agents need more colours than a gif palette holds.
+    TooManyColors(usize),
+}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::Io { path, source } => {
+                write!(f, "unable to write {}: {}", path, source)
+            }
+            RenderError::Png(source) => {
+                write!(f, "unable to encode png: {}", source)
+            }
+            RenderError::Gif(source) => {
+                write!(f, "unable to encode gif: {}", source)
+            }
+            RenderError::TooLarge { width, height } => {
+                write!(
+                    f,
+                    "{}x{} image exceeds the gif limit of 65535 pixels per side",
+                    width, height
+                )
+            }
+            RenderError::SizeMismatch { expected, found } => {
+                write!(
+                    f,
+                    "recorded map is {}x{} but frames are {}x{}",
+                    found.0, found.1, expected.0, expected.1
+                )
+            }
+            RenderError::TooManyColors(colors) => {
+                write!(
+                    f,
+                    "{} colours exceed the gif limit of {}",
+                    colors, GIF_COLORS
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::Io { source, .. } => Some(source),
+            RenderError::Png(source) => Some(source),
+            RenderError::Gif(source) => Some(source),
+            RenderError::TooLarge { .. }
+            | RenderError::SizeMismatch { .. }
+            | RenderError::TooManyColors(_) => None,
+        }
+    }
+}
+
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> RenderError + '_ {
+    move |source| RenderError::Io {
+        path: path.display().to_string(),
+        source,
+    }
+}
+
+fn to_rgba(color: Color) -> [u8; 4] {
+    color.map(|component| (component.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// Cells to paint as `(i, j, colour)`, items first and then agents.
+fn cells(sim: &Sim, options: RenderOptions) -> Vec<(usize, usize, Color)> {
+    let mut cells = vec![];
+    for (i, row) in sim.mapa.iter().enumerate() {
+        for (j, cel) in row.iter().enumerate() {
+            if !cel.is_empty() {
+                cells.push((i, j, group_color(cel.label)));
+            }
+        }
+    }
+    if options.agents {
+        for agent in sim.agents.iter() {
+            let pos = agent.get_pos();
+            cells.push((pos.i, pos.j, agent_color(agent.get_state())));
+        }
+    }
+    cells
+}
+
+/// Horizontal shift of row `i`, in pixels: odd rows of a hexagonal grid are
+/// drawn half a cell to the right.
+fn row_shift(sim: &Sim, i: usize, size: usize) -> usize {
+    match sim.config.grid {
+        Grid::Hex if i % 2 == 1 => size / 2,
+        _ => 0,
+    }
+}
+
+/// Size of the rendered map, in pixels.
+fn dimensions(sim: &Sim, size: usize) -> (usize, usize) {
+    let width = sim.config.mapa_width * size + row_shift(sim, 1, size);
+    (width, sim.config.mapa_height * size)
+}
+
+/// RGBA raster of a map, row by row.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let start = (y * self.width + x) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[start..start + 4]);
+        pixel
+    }
+
+    pub fn write_png(&self, path: &Path) -> Result<(), RenderError> {
+        let file = fs::File::create(path).map_err(io_error(path))?;
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(RenderError::Png)?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(RenderError::Png)?;
+        writer.finish().map_err(RenderError::Png)
+    }
+}
+
+/// Rasterises the map of `sim`, one `cell_size` square per cell, with the
+/// odd rows of a hexagonal grid shifted half a cell.
+pub fn rasterize(sim: &Sim, options: RenderOptions) -> Image {
+    let size = options.cell_size.max(1);
+    let (width, height) = dimensions(sim, size);
+    let mut pixels = to_rgba(BACKGROUND).repeat(width * height);
+    for (i, j, color) in cells(sim, options) {
+        let rgba = to_rgba(color);
+        let x = j * size + row_shift(sim, i, size);
+        for y in i * size..(i + 1) * size {
+            let start = (y * width + x) * 4;
+            for pixel in pixels[start..start + size * 4].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&rgba);
+            }
+        }
+    }
+    Image {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// Renders the map of `sim` as an SVG document, laid out like
+/// [`rasterize`].
+pub fn svg(sim: &Sim, options: RenderOptions) -> String {
+    let size = options.cell_size.max(1);
+    let (width, height) = dimensions(sim, size);
+    let hex = |color: Color| {
+        let [r, g, b, _] = to_rgba(color);
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    };
+    let mut svg = String::new();
+    // Writing into a String cannot fail.
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
+        width, height, width, height
+    );
+    let _ = writeln!(
+        svg,
+        "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+        width,
+        height,
+        hex(BACKGROUND)
+    );
+    for (i, j, color) in cells(sim, options) {
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            j * size + row_shift(sim, i, size),
+            i * size,
+            size,
+            size,
+            hex(color)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn write_png(path: &Path, sim: &Sim, options: RenderOptions) -> Result<(), RenderError> {
+    rasterize(sim, options).write_png(path)
+}
+
+pub fn write_svg(path: &Path, sim: &Sim, options: RenderOptions) -> Result<(), RenderError> {
+    fs::write(path, svg(sim, options)).map_err(io_error(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent::DecisionModelKind;
+    use data_retrieve::{Normalization, Sample};
+    use map::DataSource;
+    use object::DistanceMetric;
+    use simulation::SimConfig;
+
+    pub(crate) fn small_sim() -> Sim {
+        let items = (0..6)
+            .map(|id| Sample {
+                id,
+                features: vec![id as f64],
+                label: Some(1 + (id % 2) as u8),
+            })
+            .collect();
+        Sim::create(SimConfig {
+            max_iters: 20,
+            mapa_height: 4,
+            mapa_width: 5,
+            qtd_agents: 2,
+            agent_vision_radius: 1,
+            seed: Some(1),
+            k1: agent::DEFAULT_K1,
+            k2: agent::DEFAULT_K2,
+            alpha: agent::DEFAULT_ALPHA,
+            estimate_alpha: false,
+            normalization: Normalization::None,
+            decision_model: DecisionModelKind::default(),
+            distance_metric: DistanceMetric::default(),
+            topology: agent::Topology::default(),
+            grid: agent::Grid::default(),
+            data: DataSource::Items(items),
+        })
+    }
+
+    #[test]
+    fn every_group_has_its_own_color() {
+        let mut colors: Vec<Color> = vec![];
+        for data in data_retrieve::get_data("../data/data_2.txt").unwrap() {
+            let color = group_color(Some(data.group));
+            if !colors.contains(&color) {
+                colors.push(color);
+            }
+        }
+        assert_eq!(colors.len(), 15);
+        assert!(!colors.contains(&group_color(None)));
+    }
+
+    #[test]
+    fn draws_every_item() {
+        let sim = small_sim();
+        let options = RenderOptions {
+            cell_size: 3,
+            agents: false,
+        };
+        let image = rasterize(&sim, options);
+        assert_eq!((image.width, image.height), (15, 12));
+        let background = to_rgba(BACKGROUND);
+        let painted = image
+            .pixels
+            .chunks_exact(4)
+            .filter(|pixel| *pixel != background)
+            .count();
+        assert_eq!(painted, 6 * 9);
+        for (i, row) in sim.mapa.iter().enumerate() {
+            for (j, cel) in row.iter().enumerate() {
+                let expected = match cel.is_empty() {
+                    true => background,
+                    false => to_rgba(group_color(cel.label)),
+                };
+                assert_eq!(image.pixel(j * 3 + 2, i * 3 + 1), expected);
+            }
+        }
+
+        let document = svg(&sim, options);
+        assert_eq!(document.matches("<rect ").count(), 1 + 6);
+        let with_agents = RenderOptions {
+            agents: true,
+            ..options
+        };
+        assert_eq!(svg(&sim, with_agents).matches("<rect ").count(), 1 + 6 + 2);
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    fs,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use agent::AgentStates;
+use object::Object;
+use simulation::Sim;
+
+use crate::{
+    agent_color, group_color, io_error, rasterize, to_rgba, Image, RenderError, RenderOptions,
+    BACKGROUND, GIF_COLORS,
+};
+
+/// Every colour `rasterize` can paint for the items of `sim`, used as the
+/// global GIF palette.
+fn palette(sim: &Sim) -> Vec<[u8; 4]> {
+    let mut colors = vec![to_rgba(BACKGROUND)];
+    let carried = sim.agents.iter().map(|agent| &agent.backpack);
+    let groups = sim
+        .mapa
+        .iter()
+        .flatten()
+        .chain(carried)
+        .filter(|item| !item.is_empty())
+        .map(|item| group_color(item.label));
+    let agents = [
+        AgentStates::CARRYING,
+        AgentStates::SEARCHING,
+        AgentStates::DONE,
+    ]
+    .map(agent_color);
+    for color in groups.chain(agents) {
+        let rgba = to_rgba(color);
+        if !colors.contains(&rgba) {
+            colors.push(rgba);
+        }
+    }
+    colors
+}
+
+enum Sink {
+    Frames(PathBuf),
+    Gif {
+        encoder: gif::Encoder<BufWriter<fs::File>>,
+        palette: Vec<[u8; 4]>,
+        delay: u16,
+    },
+}
+
+/// Captures the map of a running simulation every `every` updates, either
+/// as numbered PNG frames or as the frames of an animated GIF.
+pub struct Recorder {
+    sink: Sink,
+    every: usize,
+    options: RenderOptions,
+    width: usize,
+    height: usize,
+    frames: usize,
+    last: Option<usize>,
+}
+
+impl Recorder {
+    /// Writes `frame_00000.png`, `frame_00001.png`, ... into `dir`, creating
+    /// it if needed.
+    pub fn png_frames(
+        dir: &Path,
+        sim: &Sim,
+        every: usize,
+        options: RenderOptions,
+    ) -> Result<Self, RenderError> {
+        fs::create_dir_all(dir).map_err(io_error(dir))?;
+        Ok(Recorder::new(
+            Sink::Frames(dir.to_path_buf()),
+            sim,
+            every,
+            options,
+        ))
+    }
+
+    /// Writes a looping GIF to `path`, creating its directory if needed and
+    /// showing every frame for `delay` hundredths of a second.
+    pub fn gif(
+        path: &Path,
+        sim: &Sim,
+        every: usize,
+        options: RenderOptions,
+        delay: u16,
+    ) -> Result<Self, RenderError> {
+        let image = rasterize(sim, options);
+        let (width, height) = match (u16::try_from(image.width), u16::try_from(image.height)) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => {
+                return Err(RenderError::TooLarge {
+                    width: image.width,
+                    height: image.height,
+                })
+            }
+        };
+        let palette = palette(sim);
+        if palette.len() > GIF_COLORS {
+            return Err(RenderError::TooManyColors(palette.len()));
+        }
+        let global = palette
+            .iter()
+            .flat_map(|rgba| [rgba[0], rgba[1], rgba[2]])
+            .collect::<Vec<u8>>();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io_error(dir))?;
+        }
+        let file = fs::File::create(path).map_err(io_error(path))?;
+        let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &global)
+            .map_err(RenderError::Gif)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(RenderError::Gif)?;
+        let sink = Sink::Gif {
+            encoder,
+            palette,
+            delay,
+        };
+        Ok(Recorder::new(sink, sim, every, options))
+    }
+
+    fn new(sink: Sink, sim: &Sim, every: usize, options: RenderOptions) -> Self {
+        Recorder {
+            sink,
+            every: every.max(1),
+            options,
+            width: sim.config.mapa_width,
+            height: sim.config.mapa_height,
+            frames: 0,
+            last: None,
+        }
+    }
+
+    /// Captures a frame when the number of updates of `sim` so far is a
+    /// multiple of `every`. Returns whether a frame was captured.
+    pub fn record(&mut self, sim: &Sim) -> Result<bool, RenderError> {
+        if !(sim.get_iter() + sim.extra_iters).is_multiple_of(self.every) {
+            return Ok(false);
+        }
+        self.capture(sim)
+    }
+
+    /// Captures a frame regardless of the iteration, unless the current
+    /// update of `sim` was captured already. Returns whether a frame was
+    /// captured.
+    pub fn capture(&mut self, sim: &Sim) -> Result<bool, RenderError> {
+        let updates = sim.get_iter() + sim.extra_iters;
+        if self.last == Some(updates) {
+            return Ok(false);
+        }
+        let size = (sim.config.mapa_height, sim.config.mapa_width);
+        if size != (self.height, self.width) {
+            return Err(RenderError::SizeMismatch {
+                expected: (self.height, self.width),
+                found: size,
+            });
+        }
+        let image = rasterize(sim, self.options);
+        match &mut self.sink {
+            Sink::Frames(dir) => {
+                image.write_png(&dir.join(format!("frame_{:05}.png", self.frames)))?;
+            }
+            Sink::Gif {
+                encoder,
+                palette,
+                delay,
+            } => {
+                let mut frame = gif::Frame::from_indexed_pixels(
+                    image.width as u16,
+                    image.height as u16,
+                    indices(&image, palette),
+                    None,
+                );
+                frame.delay = *delay;
+                encoder.write_frame(&frame).map_err(RenderError::Gif)?;
+            }
+        }
+        self.frames += 1;
+        self.last = Some(updates);
+        Ok(true)
+    }
+
+    /// Number of frames captured so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+fn indices(image: &Image, palette: &[[u8; 4]]) -> Vec<u8> {
+    image
+        .pixels
+        .chunks_exact(4)
+        .map(|pixel| {
+            palette
+                .iter()
+                .position(|rgba| rgba[..] == *pixel)
+                .unwrap_or(0) as u8
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::small_sim;
+    use data_retrieve::Sample;
+
+    #[test]
+    fn records_every_n_updates() {
+        let mut sim = small_sim();
+        let dir = std::env::temp_dir().join(format!("frames-{}", std::process::id()));
+        let gif_path = dir.with_extension("gif");
+        let options = RenderOptions {
+            cell_size: 2,
+            agents: true,
+        };
+        let mut frames = Recorder::png_frames(&dir, &sim, 3, options).unwrap();
+        let mut gif = Recorder::gif(&gif_path, &sim, 3, options, 5).unwrap();
+        for _ in 0..10 {
+            sim.update();
+            frames.record(&sim).unwrap();
+            gif.record(&sim).unwrap();
+        }
+        assert_eq!(frames.frames(), 3);
+        assert_eq!(gif.frames(), 3);
+        assert!(dir.join("frame_00002.png").exists());
+        assert!(!dir.join("frame_00003.png").exists());
+        sim.update();
+        sim.config.mapa_width += 1;
+        assert!(matches!(
+            frames.capture(&sim),
+            Err(RenderError::SizeMismatch {
+                expected: (4, 5),
+                found: (4, 6)
+            })
+        ));
+        drop(gif);
+
+        let decoder = gif::DecodeOptions::new()
+            .read_info(fs::File::open(&gif_path).unwrap())
+            .unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (10, 8));
+        assert_eq!(decoder.into_iter().count(), 3);
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_file(&gif_path).unwrap();
+    }
+
+    #[test]
+    fn rejects_more_colors_than_a_gif_palette() {
+        let mut sim = small_sim();
+        sim.config.mapa_height = 16;
+        sim.config.mapa_width = 16;
+        sim.mapa = (0..16)
+            .map(|i| {
+                (0..16)
+                    .map(|j| Sample {
+                        id: i * 16 + j,
+                        features: vec![0.0],
+                        label: Some((i * 16 + j) as u8),
+                    })
+                    .collect()
+            })
+            .collect();
+        let path = std::env::temp_dir().join(format!("colors-{}.gif", std::process::id()));
+        let options = RenderOptions {
+            cell_size: 1,
+            agents: true,
+        };
+        assert!(matches!(
+            Recorder::gif(&path, &sim, 1, options, 5),
+            Err(RenderError::TooManyColors(colors)) if colors > GIF_COLORS
+        ));
+        assert!(!path.exists());
+    }
+}
@@ -1,5 +1,78 @@
+use std::sync::Arc;
+
+pub type DistanceFn = dyn Fn(&[f64], &[f64]) -> f64 + Send + Sync;
+
+/// Distance between the feature vectors of two objects. Vectors of different
+/// lengths are compared over their common prefix.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DistanceMetric {
+    #[default]
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+    /// `1 - cos(a, b)`, or `1` when either vector is zero.
+    Cosine,
+    /// Minkowski distance of order `p`, which must be at least 1.
+    Minkowski(f64),
+    /// Not serializable.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<DistanceFn>),
+}
+
+impl DistanceMetric {
+    pub fn distance(&self, a: &[f64], b: &[f64]) -> f64 {
+        let pairs = a.iter().zip(b.iter());
+        match self {
+            DistanceMetric::Euclidean => pairs.map(|(x, y)| (x - y) * (x - y)).sum::<f64>().sqrt(),
+            DistanceMetric::Manhattan => pairs.map(|(x, y)| (x - y).abs()).sum(),
+            DistanceMetric::Chebyshev => pairs.map(|(x, y)| (x - y).abs()).fold(0.0, f64::max),
+            DistanceMetric::Cosine => {
+                let (dot, norm_a, norm_b) = pairs.fold((0.0, 0.0, 0.0), |acc, (x, y)| {
+                    (acc.0 + x * y, acc.1 + x * x, acc.2 + y * y)
+                });
+                let norm = (norm_a * norm_b).sqrt();
+                if norm == 0.0 {
+                    return 1.0;
+                }
+                1.0 - dot / norm
+            }
+            DistanceMetric::Minkowski(p) => pairs
+                .map(|(x, y)| (x - y).abs().powf(*p))
+                .sum::<f64>()
+                .powf(1.0 / p),
+            DistanceMetric::Custom(distance) => distance(a, b),
+        }
+    }
+}
+
 pub trait Object {
     fn is_empty(&self) -> bool;
     fn clone_empty() -> Self;
-    fn get_distance(&self, other_obj: &Self) -> f64;
+    fn get_distance(&self, other_obj: &Self, metric: &DistanceMetric) -> f64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics() {
+        let a = [0.0, 0.0];
+        let b = [3.0, 4.0];
+        assert_eq!(DistanceMetric::Euclidean.distance(&a, &b), 5.0);
+        assert_eq!(DistanceMetric::Manhattan.distance(&a, &b), 7.0);
+        assert_eq!(DistanceMetric::Chebyshev.distance(&a, &b), 4.0);
+        assert_eq!(DistanceMetric::Minkowski(1.0).distance(&a, &b), 7.0);
+        assert!((DistanceMetric::Minkowski(2.0).distance(&a, &b) - 5.0).abs() < 1e-12);
+        assert_eq!(DistanceMetric::Cosine.distance(&a, &b), 1.0);
+        assert!(
+            DistanceMetric::Cosine
+                .distance(&[1.0, 1.0], &[2.0, 2.0])
+                .abs()
+                < 1e-12
+        );
+        let custom = DistanceMetric::Custom(Arc::new(|a, b| (a[0] - b[0]).abs()));
+        assert_eq!(custom.distance(&a, &b), 3.0);
+    }
 }
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use object::Object;
+
+use crate::{tracking::backpack_id, Sim, SimError};
+
+impl Sim {
+    /// Checks that the items on the map plus the items in the backpacks are
+    /// exactly the input items: every id once, and as many items of every
+    /// label as in the input.
+    pub fn check_items(&self) -> Result<(), SimError> {
+        let iteration = self.iter_atual + self.extra_iters;
+        let mut seen = vec![false; self.labels.len()];
+        let mut counts: BTreeMap<Option<u8>, usize> = BTreeMap::new();
+        let ground = self
+            .mapa
+            .iter()
+            .flatten()
+            .filter(|cel| !cel.is_empty())
+            .map(|cel| (cel.id, cel.label));
+        let carried = self
+            .agents
+            .iter()
+            .filter_map(|agent| backpack_id(agent).map(|id| (id, agent.backpack.label)));
+        for (item, label) in ground.chain(carried) {
+            match seen.get_mut(item) {
+                None => return Err(SimError::UnknownItem { iteration, item }),
+                Some(true) => return Err(SimError::ItemDuplicated { iteration, item }),
+                Some(seen) => *seen = true,
+            }
+            *counts.entry(label).or_default() += 1;
+        }
+        if let Some(item) = seen.iter().position(|seen| !seen) {
+            return Err(SimError::ItemLost { iteration, item });
+        }
+        let mut expected: BTreeMap<Option<u8>, usize> = BTreeMap::new();
+        for label in self.labels.iter() {
+            *expected.entry(*label).or_default() += 1;
+        }
+        for (label, expected) in expected {
+            let found = counts.get(&label).copied().unwrap_or(0);
+            if found != expected {
+                return Err(SimError::GroupCount {
+                    iteration,
+                    label,
+                    expected,
+                    found,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes [`Sim::try_update`] also run [`Sim::check_items`] after every
+    /// `every` updates, or never with `None`.
+    pub fn check_items_every(&mut self, every: Option<usize>) {
+        self.item_check_every = every.map(|every| every.max(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::config, SimState};
+    use data_retrieve::Sample;
+
+    #[test]
+    fn catches_lost_and_duplicated_items() {
+        let mut sim = Sim::create(config(4));
+        sim.check_items_every(Some(100));
+        while sim.get_state() != SimState::DONE {
+            sim.try_update().unwrap();
+        }
+        sim.check_items().unwrap();
+
+        let cells = (0..40 * 40).map(|cell| (cell / 40, cell % 40));
+        let empty = cells
+            .clone()
+            .find(|&(i, j)| sim.mapa[i][j].is_empty())
+            .unwrap();
+        let (i, j) = cells
+            .clone()
+            .find(|&(i, j)| !sim.mapa[i][j].is_empty())
+            .unwrap();
+        let id = sim.mapa[i][j].id;
+        sim.mapa[empty.0][empty.1] = sim.mapa[i][j].clone();
+        assert_eq!(
+            sim.check_items(),
+            Err(SimError::ItemDuplicated {
+                iteration: sim.get_iter() + sim.extra_iters,
+                item: id
+            })
+        );
+        sim.mapa[empty.0][empty.1].id = sim.labels.len();
+        assert!(matches!(
+            sim.check_items(),
+            Err(SimError::UnknownItem { .. })
+        ));
+        sim.mapa[empty.0][empty.1] = Sample::default();
+        sim.mapa[i][j].label = sim.mapa[i][j].label.map(|label| label + 1);
+        assert!(matches!(
+            sim.check_items(),
+            Err(SimError::GroupCount { .. })
+        ));
+        sim.mapa[i][j] = Sample::default();
+        assert!(matches!(
+            sim.check_items(),
+            Err(SimError::ItemLost { item, .. }) if item == id
+        ));
+    }
+}
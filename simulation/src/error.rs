@@ -0,0 +1,67 @@
+use std::fmt::Display;
+
+use agent::Invariant;
+
+/// Inconsistency found by a checked update, see [`crate::Sim::try_update`],
+/// or by [`crate::Sim::check_items`].
+/// `iteration` counts every update of the simulation, finishing ones
+/// included.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SimError {
+    Agent {
+        iteration: usize,
+        agent: usize,
+        invariant: Invariant,
+    },
+    /// An item is neither on the map nor in a backpack.
+    ItemLost { iteration: usize, item: usize },
+    /// An item is in more than one cell or backpack.
+    ItemDuplicated { iteration: usize, item: usize },
+    /// An item id that is not in the input data.
+    UnknownItem { iteration: usize, item: usize },
+    /// The number of items with `label` differs from the input data.
+    GroupCount {
+        iteration: usize,
+        label: Option<u8>,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for SimError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimError::Agent {
+                iteration,
+                agent,
+                invariant,
+            } => {
+                write!(f, "iteration {}: agent {} {}", iteration, agent, invariant)
+            }
+            SimError::ItemLost { iteration, item } => {
+                write!(f, "iteration {}: item {} was lost", iteration, item)
+            }
+            SimError::ItemDuplicated { iteration, item } => {
+                write!(f, "iteration {}: item {} was duplicated", iteration, item)
+            }
+            SimError::UnknownItem { iteration, item } => {
+                write!(f, "iteration {}: unknown item {}", iteration, item)
+            }
+            SimError::GroupCount {
+                iteration,
+                label,
+                expected,
+                found,
+            } => write!(
+                f,
+                "iteration {}: expected {} items labelled {} but found {}",
+                iteration,
+                expected,
+                label.map_or("none".to_string(), |label| label.to_string()),
+                found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
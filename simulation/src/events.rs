@@ -0,0 +1,203 @@
+use std::io::{self, Write};
+
+use agent::{Agent, AgentStates, Point};
+use data_retrieve::Sample;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum EventKind {
+    Pick,
+    Drop,
+    /// Last drop of an agent after `max_iters`, which leaves it done.
+    Finish,
+}
+
+impl EventKind {
+    fn name(&self) -> &'static str {
+        match self {
+            EventKind::Pick => "pick",
+            EventKind::Drop => "drop",
+            EventKind::Finish => "finish",
+        }
+    }
+}
+
+/// The pick-up or drop `agent` performed during its last update and the id
+/// of the item it moved, given its state and backpack item before that
+/// update.
+pub(crate) fn item_move(
+    agent: &Agent<Sample>,
+    old_state: AgentStates,
+    old_backpack: Option<usize>,
+) -> Option<(EventKind, usize)> {
+    match (old_state, agent.get_state(), old_backpack) {
+        (AgentStates::SEARCHING, AgentStates::CARRYING, _) => {
+            Some((EventKind::Pick, agent.backpack.id))
+        }
+        (AgentStates::CARRYING, AgentStates::SEARCHING, Some(id)) => Some((EventKind::Drop, id)),
+        (AgentStates::FINISHING, AgentStates::DONE, Some(id)) => Some((EventKind::Finish, id)),
+        _ => None,
+    }
+}
+
+/// A pick-up or drop performed by an agent. `iteration` counts every update
+/// of the simulation, finishing ones included, and `cell` is where the item
+/// was picked up or dropped.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Event {
+    pub iteration: usize,
+    pub agent: usize,
+    pub kind: EventKind,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub cell: Point,
+    pub item: usize,
+    pub density: f64,
+    pub probability: f64,
+}
+
+/// Output format of an [`EventWriter`]. JSON lines need the `serde` feature.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum EventFormat {
+    #[cfg(feature = "serde")]
+    #[default]
+    JsonLines,
+    #[cfg_attr(not(feature = "serde"), default)]
+    Csv,
+}
+
+/// Writes events one per line, as JSON objects or CSV rows.
+pub struct EventWriter<W: Write> {
+    writer: W,
+    format: EventFormat,
+}
+
+impl<W: Write> EventWriter<W> {
+    /// Creates the writer, writing the CSV header right away.
+    pub fn new(mut writer: W, format: EventFormat) -> io::Result<Self> {
+        if format == EventFormat::Csv {
+            writeln!(writer, "iteration,agent,kind,i,j,item,density,probability")?;
+        }
+        Ok(EventWriter { writer, format })
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        match self.format {
+            #[cfg(feature = "serde")]
+            EventFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, event)?;
+                writeln!(self.writer)
+            }
+            EventFormat::Csv => writeln!(
+                self.writer,
+                "{},{},{},{},{},{},{},{}",
+                event.iteration,
+                event.agent,
+                event.kind.name(),
+                event.cell.i,
+                event.cell.j,
+                event.item,
+                event.density,
+                event.probability
+            ),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Buffers the events of the simulation while enabled.
+#[derive(Default)]
+pub(crate) struct EventLog {
+    pub(crate) enabled: bool,
+    pub(crate) events: Vec<Event>,
+}
+
+impl EventLog {
+    /// Records the move of `item`, see [`item_move`], performed by agent
+    /// number `index` at `pos` during its last update.
+    pub(crate) fn record(
+        &mut self,
+        iteration: usize,
+        index: usize,
+        agent: &Agent<Sample>,
+        kind: EventKind,
+        item: usize,
+        pos: Point,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let decision = match agent.last_decision() {
+            Some(decision) => decision,
+            None => return,
+        };
+        self.events.push(Event {
+            iteration,
+            agent: index,
+            kind,
+            cell: pos,
+            item,
+            density: decision.density,
+            probability: decision.probability,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::config, Sim, SimState};
+
+    #[test]
+    fn logs_every_move() {
+        let mut sim = Sim::create(config(5));
+        sim.record_events(true);
+        let mut events = vec![];
+        while sim.get_state() != SimState::DONE {
+            sim.update();
+            events.extend(sim.take_events());
+        }
+        let count = |kind| events.iter().filter(|event| event.kind == kind).count();
+        let picks: usize = sim.item_stats().iter().map(|stats| stats.pick_ups).sum();
+        assert_eq!(count(EventKind::Pick), picks);
+        assert_eq!(count(EventKind::Drop) + count(EventKind::Finish), picks);
+        assert!(events
+            .iter()
+            .all(|event| (0.0..=1.0).contains(&event.probability)));
+        assert!(events.windows(2).all(|w| w[0].iteration <= w[1].iteration));
+
+        let mut writer = EventWriter::new(vec![], EventFormat::Csv).unwrap();
+        writer.write(&events[0]).unwrap();
+        let csv = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.lines().nth(1).unwrap().contains(",pick,"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn writes_json_lines() {
+        let event = Event {
+            iteration: 12,
+            agent: 3,
+            kind: EventKind::Drop,
+            cell: Point { i: 4, j: 5 },
+            item: 7,
+            density: 0.125,
+            probability: 0.3,
+        };
+        let mut writer = EventWriter::new(vec![], EventFormat::JsonLines).unwrap();
+        writer.write(&event).unwrap();
+        writer.write(&event).unwrap();
+        let json = String::from_utf8(writer.into_inner()).unwrap();
+        let line = json.lines().next().unwrap();
+        assert_eq!(json.lines().count(), 2);
+        assert!(
+            line.starts_with("{\"iteration\":12,\"agent\":3,\"kind\":\"drop\",\"i\":4,\"j\":5,")
+        );
+        assert_eq!(serde_json::from_str::<Event>(line).unwrap(), event);
+    }
+}
@@ -1,108 +1,162 @@
-use agent::Agent;
-use data_retrieve::Data;
-use map::{init_objs, MapaDef};
+use agent::{vision_center, Agent, DecisionModelKind, Grid, Topology};
+use data_retrieve::{Normalization, Sample};
+use map::{init_objs, DataSource, MapError, MapaDef};
+use object::{DistanceMetric, Object};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+mod conservation;
+mod error;
+mod events;
+pub mod metrics;
+#[cfg(feature = "serde")]
+mod snapshot;
+pub mod stats;
+pub mod sweep;
+mod tracking;
+mod validation;
+
+use events::{item_move, EventLog};
+use metrics::{evaluate, spatial_clusters, ClusteringMetrics, DEFAULT_ENTROPY_BLOCK};
+use tracking::{backpack_id, ItemTracker};
+use validation::Prepared;
+
+pub use error::SimError;
+pub use events::{Event, EventFormat, EventKind, EventWriter};
+
+#[cfg(feature = "serde")]
+pub use snapshot::SnapshotError;
+pub use tracking::{ItemLocation, ItemStats};
+pub use validation::{ConfigError, MIN_MAP_SIDE};
+
+pub type SimRng = ChaCha8Rng;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SimState {
     RUNNING,
     FINISHING,
     DONE,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimConfig {
     pub max_iters: usize,
     pub mapa_height: usize,
     pub mapa_width: usize,
     pub qtd_agents: usize,
     pub agent_vision_radius: usize,
+    pub seed: Option<u64>,
+    pub k1: f64,
+    pub k2: f64,
+    pub alpha: f64,
+    /// Replaces `alpha` with the mean pairwise distance of the normalised data.
+    pub estimate_alpha: bool,
+    pub normalization: Normalization,
+    pub decision_model: DecisionModelKind,
+    pub distance_metric: DistanceMetric,
+    /// How the map edges behave for the agents and the cluster extraction.
+    pub topology: Topology,
+    pub grid: Grid,
+    pub data: DataSource,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sim {
     pub mapa: MapaDef,
-    pub agents: Vec<Agent<Data>>,
+    pub agents: Vec<Agent<Sample>>,
     state: SimState,
     iter_atual: usize,
     pub extra_iters: usize,
     pub config: SimConfig,
+    qtd_items: usize,
+    /// Label of every input item, indexed by input position.
+    labels: Vec<Option<u8>>,
+    alpha: f64,
+    tracker: ItemTracker,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    events: EventLog,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    item_check_every: Option<usize>,
+    rng: SimRng,
 }
 
 impl Sim {
+    /// Creates the simulation, panicking when the configuration is invalid.
+    /// See [`Sim::try_create`].
     pub fn create(config: SimConfig) -> Self {
-        let mapa = init_objs(config.mapa_height, config.mapa_width);
+        Sim::try_create(config).unwrap_or_else(|err| panic!("Invalid configuration: {}", err))
+    }
+
+    /// Creates the simulation, or reports why the configuration cannot run.
+    pub fn try_create(config: SimConfig) -> Result<Self, ConfigError> {
+        let Prepared {
+            data,
+            alpha,
+            agent_config,
+        } = config.prepare()?;
+        let mut rng = match config.seed {
+            Some(seed) => SimRng::seed_from_u64(seed),
+            None => SimRng::from_entropy(),
+        };
+        let mapa = init_objs(config.mapa_height, config.mapa_width, &data, &mut rng).map_err(
+            |MapError::NotEnoughCells { cells, items }| ConfigError::NotEnoughCells {
+                cells,
+                items,
+            },
+        )?;
         // show_mapa(&mapa, mapa_width);
-        let agents = Agent::create_agents(
-            config.agent_vision_radius,
-            config.qtd_agents,
+        let agents = Agent::create_agents(agent_config, config.qtd_agents, &mut rng);
+        let tracker = ItemTracker::new(
+            data.len(),
             config.mapa_height,
             config.mapa_width,
+            config.topology,
+            config.grid,
         );
-        Self {
+        Ok(Self {
             mapa,
             agents,
             state: SimState::RUNNING,
             iter_atual: 0,
             extra_iters: 0,
             config,
-        }
+            qtd_items: data.len(),
+            labels: data.iter().map(|sample| sample.label).collect(),
+            alpha,
+            tracker,
+            events: EventLog::default(),
+            item_check_every: None,
+            rng,
+        })
     }
 
-    fn update_done(&self) {
-        return;
-    }
+    fn update_done(&self) {}
 
-    fn update_running(&mut self) {
+    fn update_running(&mut self, checked: bool) -> Result<(), SimError> {
         let agents = self.agents.iter_mut();
         let mapa = self.mapa.as_mut();
-        for agent in agents {
+        for (index, agent) in agents.enumerate() {
             let old_state = agent.get_state();
+            let old_backpack = backpack_id(agent);
             let pos = agent.get_pos();
-            let vision = agent.update_agent(mapa);
-            let new_state = agent.get_state();
-            if new_state != old_state {
-                match new_state {
-                    agent::AgentStates::CARRYING => {
-                        assert_ne!(agent.backpack.group, 0);
-                        assert_ne!(mapa[pos.i][pos.j].group, 0);
-                        assert_eq!(vision[1][1].group, 0);
-                    }
-                    agent::AgentStates::SEARCHING => {
-                        assert_eq!(agent.backpack.group, 0);
-                        assert_eq!(mapa[pos.i][pos.j].group, 0);
-                        assert_ne!(vision[1][1].group, 0);
-                    }
-                    agent::AgentStates::FINISHING => {
-                        assert_ne!(agent.backpack.group, 0);
-                        assert_ne!(mapa[pos.i][pos.j].group, 0);
-                        assert_eq!(vision[1][1].group, 0);
-                    }
-                    agent::AgentStates::DONE => {
-                        assert_eq!(agent.backpack.group, 0);
-                        assert_eq!(mapa[pos.i][pos.j].group, 0);
-                        assert_ne!(vision[1][1].group, 0);
-                    }
-                }
+            let vision = agent.update_agent(mapa, &mut self.rng);
+            if checked {
+                agent
+                    .check_update(pos, old_state, &mapa[pos.i][pos.j], &vision)
+                    .map_err(|invariant| SimError::Agent {
+                        iteration: self.iter_atual,
+                        agent: index,
+                        invariant,
+                    })?;
             }
-            mapa[pos.i][pos.j] = vision[1][1].clone();
-            // for i in 0..vision.len() {
-            //     let real_i = if pos.i + i >= 1 {
-            //         (pos.i + i - 1) % 90
-            //     } else {
-            //         90 + pos.i + i - 1
-            //     };
-            //     for j in 0..vision[i].len() {
-            //         let real_j = if pos.j + j >= 1 {
-            //             (pos.j + j - 1) % 50
-            //         } else {
-            //             50 + pos.j + j - 1
-            //         };
-            //         show_mapa(mapa, self.config.mapa_width);
-            //         println!("{} {} {} {}", i, j, real_i, real_j);
-            //         assert_eq!(mapa[real_i][real_j].x, vision[i][j].x);
-            //         assert_eq!(mapa[real_i][real_j].y, vision[i][j].y);
-            //         assert_eq!(mapa[real_i][real_j].group, vision[i][j].group);
-            //     }
-            // }
+            if let Some((kind, item)) = item_move(agent, old_state, old_backpack) {
+                self.tracker.record(kind, item, pos);
+                self.events
+                    .record(self.iter_atual, index, agent, kind, item, pos);
+            }
+            mapa[pos.i][pos.j] = vision_center(&vision).clone();
         }
         self.iter_atual += 1;
         if self.iter_atual == self.config.max_iters {
@@ -111,38 +165,267 @@ impl Sim {
                 agent.finish();
             }
         }
+        Ok(())
     }
 
-    fn update_finishing(&mut self) {
+    fn update_finishing(&mut self, checked: bool) -> Result<(), SimError> {
         let mut remaining = self
             .agents
             .iter_mut()
-            .filter(|agent| agent.is_finishing())
-            .collect::<Vec<&mut Agent<Data>>>();
+            .enumerate()
+            .filter(|(_, agent)| agent.is_finishing())
+            .collect::<Vec<(usize, &mut Agent<Sample>)>>();
         if remaining.is_empty() {
             self.state = SimState::DONE;
-            return;
+            return Ok(());
         }
-        for agent in remaining.iter_mut() {
+        let iteration = self.iter_atual + self.extra_iters;
+        for (index, agent) in remaining.iter_mut() {
+            let old_state = agent.get_state();
+            let old_backpack = backpack_id(agent);
             let pos = agent.get_pos();
-            let vision = agent.update_agent(&mut self.mapa);
-            self.mapa[pos.i][pos.j] = vision[1][1];
+            let vision = agent.update_agent(&self.mapa, &mut self.rng);
+            if checked {
+                agent
+                    .check_update(pos, old_state, &self.mapa[pos.i][pos.j], &vision)
+                    .map_err(|invariant| SimError::Agent {
+                        iteration,
+                        agent: *index,
+                        invariant,
+                    })?;
+            }
+            if let Some((kind, item)) = item_move(agent, old_state, old_backpack) {
+                self.tracker.record(kind, item, pos);
+                self.events
+                    .record(iteration, *index, agent, kind, item, pos);
+            }
+            self.mapa[pos.i][pos.j] = vision_center(&vision).clone();
         }
         self.extra_iters += 1;
+        Ok(())
     }
 
-    pub fn get_state(&self) -> SimState{
+    pub fn get_state(&self) -> SimState {
         self.state
     }
 
+    pub fn get_iter(&self) -> usize {
+        self.iter_atual
+    }
+
+    /// Dissimilarity scale used by the agents, estimated from the data when
+    /// `estimate_alpha` is set.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Scores the clusters currently on the map against the item labels.
+    /// Items still carried by agents are not counted.
+    pub fn metrics(&self) -> ClusteringMetrics {
+        evaluate(
+            &self.mapa,
+            1,
+            DEFAULT_ENTROPY_BLOCK,
+            self.config.topology,
+            self.config.grid,
+        )
+    }
+
+    /// Cluster id of every input item, indexed by its position in the input
+    /// data. Items are grouped into connected components whose members are
+    /// at most `radius` steps apart, following the map topology and grid;
+    /// items still carried by an agent get `None`.
+    pub fn cluster_assignments(&self, radius: usize) -> Vec<Option<usize>> {
+        let grid = spatial_clusters(&self.mapa, radius, self.config.topology, self.config.grid);
+        let mut assignments = vec![None; self.qtd_items];
+        for (row, ids) in self.mapa.iter().zip(grid.iter()) {
+            for (cel, id) in row.iter().zip(ids.iter()) {
+                if !cel.is_empty() {
+                    assignments[cel.id] = *id;
+                }
+            }
+        }
+        assignments
+    }
+
+    /// Current location of every input item, indexed by input position.
+    pub fn item_locations(&self) -> Vec<Option<ItemLocation>> {
+        let mut locations = vec![None; self.qtd_items];
+        for (i, row) in self.mapa.iter().enumerate() {
+            for (j, cel) in row.iter().enumerate() {
+                if !cel.is_empty() {
+                    locations[cel.id] = Some(ItemLocation::Ground(agent::Point { i, j }));
+                }
+            }
+        }
+        for (index, agent) in self.agents.iter().enumerate() {
+            if let Some(id) = backpack_id(agent) {
+                locations[id] = Some(ItemLocation::Carried { agent: index });
+            }
+        }
+        locations
+    }
+
+    /// Pick-up, drop and travelled distance counters of every input item,
+    /// indexed by input position.
+    pub fn item_stats(&self) -> &[ItemStats] {
+        self.tracker.stats()
+    }
+
+    /// Starts or stops buffering the pick-up and drop events of the agents.
+    pub fn record_events(&mut self, enabled: bool) {
+        self.events.enabled = enabled;
+    }
+
+    /// Returns and clears the events buffered since the last call.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events.events)
+    }
+
     pub fn update(&mut self) {
+        if let Err(err) = self.step(false) {
+            unreachable!("unchecked update failed: {}", err);
+        }
+    }
+
+    /// Same as [`Sim::update`], but checks every agent move, and the items
+    /// when enabled by [`Sim::check_items_every`]. Stops at the first broken
+    /// invariant, leaving the simulation in the middle of the update.
+    pub fn try_update(&mut self) -> Result<(), SimError> {
+        self.step(true)?;
+        match self.item_check_every {
+            Some(every) if (self.iter_atual + self.extra_iters).is_multiple_of(every) => {
+                self.check_items()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn step(&mut self, checked: bool) -> Result<(), SimError> {
         match self.state {
-            SimState::RUNNING => self.update_running(),
-            SimState::FINISHING => self.update_finishing(),
-            SimState::DONE => self.update_done(),
+            SimState::RUNNING => self.update_running(checked),
+            SimState::FINISHING => self.update_finishing(checked),
+            SimState::DONE => {
+                self.update_done();
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent::{DEFAULT_ALPHA, DEFAULT_K1, DEFAULT_K2};
+    use data_retrieve::get_samples;
+
+    pub(crate) fn config(seed: u64) -> SimConfig {
+        SimConfig {
+            max_iters: 2_000,
+            mapa_height: 40,
+            mapa_width: 40,
+            qtd_agents: 10,
+            agent_vision_radius: 1,
+            seed: Some(seed),
+            k1: DEFAULT_K1,
+            k2: DEFAULT_K2,
+            alpha: DEFAULT_ALPHA,
+            estimate_alpha: false,
+            normalization: Normalization::None,
+            decision_model: DecisionModelKind::default(),
+            distance_metric: DistanceMetric::default(),
+            topology: Topology::default(),
+            grid: Grid::default(),
+            data: DataSource::Items(get_samples("../data/data_1.txt", true).unwrap()),
         }
-        if self.iter_atual % 10000 == 0 {
-            println!("{} {}", self.iter_atual, self.extra_iters);
+    }
+
+    fn groups(sim: &Sim) -> Vec<Vec<Option<u8>>> {
+        sim.mapa
+            .iter()
+            .map(|row| row.iter().map(|cel| cel.label).collect())
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_map() {
+        let mut first = Sim::create(config(7));
+        let mut second = Sim::create(config(7));
+        while first.get_state() != SimState::DONE {
+            first.update();
+            second.update();
         }
+        assert!(second.get_state() == SimState::DONE);
+        assert_eq!(groups(&first), groups(&second));
+        assert_eq!(first.extra_iters, second.extra_iters);
+        assert_eq!(first.metrics(), second.metrics());
+
+        let assignments = first.cluster_assignments(1);
+        assert_eq!(assignments.len(), 400);
+        assert!(assignments.iter().all(|id| id.is_some()));
+        assert_eq!(assignments, second.cluster_assignments(1));
+        let locations = first.item_locations();
+        assert!(locations
+            .iter()
+            .all(|location| matches!(location, Some(ItemLocation::Ground(_)))));
+        let stats = first.item_stats();
+        assert!(stats.iter().all(|stat| stat.pick_ups == stat.drops));
+        assert!(stats.iter().any(|stat| stat.distance > 0.0));
+        let wide = first.cluster_assignments(3);
+        let count = |ids: &Vec<Option<usize>>| ids.iter().flatten().max().unwrap() + 1;
+        assert!(count(&wide) <= count(&assignments));
+    }
+
+    #[test]
+    fn checked_runs_on_every_lattice() {
+        let topologies = [Topology::Torus, Topology::Bounded, Topology::Reflective];
+        let cases = (1..=3).flat_map(|radius| topologies.map(|t| (radius, t)));
+        let grids = [Grid::Square, Grid::Hex];
+        for ((radius, topology), grid) in cases.flat_map(|case| grids.map(|g| (case, g))) {
+            let mut config = config(11);
+            config.agent_vision_radius = radius;
+            config.topology = topology;
+            config.grid = grid;
+            config.mapa_height = 100;
+            config.mapa_width = 100;
+            let mut sim = Sim::create(config);
+            while sim.get_state() != SimState::DONE {
+                let before: Vec<_> = sim.agents.iter().map(|agent| agent.get_pos()).collect();
+                sim.try_update().unwrap();
+                for (agent, from) in sim.agents.iter().zip(before) {
+                    let to = agent.get_pos();
+                    if to != from {
+                        let di = topology.offset(from.i, to.i, 100);
+                        let dj = topology.offset(from.j, to.j, 100);
+                        assert_eq!(
+                            grid.steps(from.i, di, dj),
+                            1,
+                            "{:?} on {:?}",
+                            topology,
+                            grid
+                        );
+                    }
+                }
+            }
+            let on_ground = sim.mapa.iter().flatten().filter(|cel| !cel.is_empty());
+            assert_eq!(on_ground.count(), 400);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut config = config(3);
+        config.normalization = Normalization::ZScore;
+        config.decision_model = DecisionModelKind::Threshold;
+        config.distance_metric = DistanceMetric::Cosine;
+        let json = serde_json::to_string(&config).unwrap();
+        let parsed: SimConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        assert_eq!(parsed.normalization, Normalization::ZScore);
+        assert!(matches!(parsed.data, DataSource::Items(ref items) if items.len() == 400));
+
+        config.distance_metric = DistanceMetric::Custom(std::sync::Arc::new(|_, _| 0.0));
+        assert!(serde_json::to_string(&config).is_err());
     }
 }
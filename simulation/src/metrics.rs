@@ -0,0 +1,365 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use agent::{Grid, Topology};
+use map::MapaDef;
+use object::Object;
+
+/// Cluster id of every cell of the map, `None` for empty cells.
+pub type ClusterGrid = Vec<Vec<Option<usize>>>;
+
+pub const DEFAULT_ENTROPY_BLOCK: usize = 5;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClusteringMetrics {
+    pub clusters: usize,
+    pub purity: f64,
+    pub rand_index: f64,
+    pub adjusted_rand_index: f64,
+    pub f_measure: f64,
+    pub nmi: f64,
+    pub spatial_entropy: f64,
+}
+
+/// Single clustering score picked out of `ClusteringMetrics`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QualityMetric {
+    Clusters,
+    #[default]
+    Purity,
+    RandIndex,
+    AdjustedRandIndex,
+    FMeasure,
+    Nmi,
+    SpatialEntropy,
+}
+
+impl QualityMetric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            QualityMetric::Clusters => "clusters",
+            QualityMetric::Purity => "purity",
+            QualityMetric::RandIndex => "rand_index",
+            QualityMetric::AdjustedRandIndex => "adjusted_rand_index",
+            QualityMetric::FMeasure => "f_measure",
+            QualityMetric::Nmi => "nmi",
+            QualityMetric::SpatialEntropy => "spatial_entropy",
+        }
+    }
+
+    pub fn value(&self, metrics: &ClusteringMetrics) -> f64 {
+        match self {
+            QualityMetric::Clusters => metrics.clusters as f64,
+            QualityMetric::Purity => metrics.purity,
+            QualityMetric::RandIndex => metrics.rand_index,
+            QualityMetric::AdjustedRandIndex => metrics.adjusted_rand_index,
+            QualityMetric::FMeasure => metrics.f_measure,
+            QualityMetric::Nmi => metrics.nmi,
+            QualityMetric::SpatialEntropy => metrics.spatial_entropy,
+        }
+    }
+}
+
+/// Groups the non-empty cells of `mapa` into connected components, where two
+/// cells are neighbours when they are at most `radius` steps apart on `grid`.
+/// Neighbours across the map edges only count on a torus.
+pub fn spatial_clusters(
+    mapa: &MapaDef,
+    radius: usize,
+    topology: Topology,
+    grid: Grid,
+) -> ClusterGrid {
+    let height = mapa.len();
+    let width = mapa.first().map_or(0, |row| row.len());
+    let mut ids: ClusterGrid = vec![vec![None; width]; height];
+    let offsets = (-(radius as isize)..=radius as isize)
+        .flat_map(|di| (-(radius as isize)..=radius as isize).map(move |dj| (di, dj)))
+        .collect::<Vec<(isize, isize)>>();
+    let mut next_id = 0;
+    for start_i in 0..height {
+        for start_j in 0..width {
+            if mapa[start_i][start_j].is_empty() || ids[start_i][start_j].is_some() {
+                continue;
+            }
+            ids[start_i][start_j] = Some(next_id);
+            let mut queue = VecDeque::from([(start_i, start_j)]);
+            while let Some((i, j)) = queue.pop_front() {
+                for &(di, dj) in offsets.iter() {
+                    if grid.steps(i, di, dj) > radius {
+                        continue;
+                    }
+                    let (ni, nj) = match (
+                        topology.neighbor(i, di, height),
+                        topology.neighbor(j, dj, width),
+                    ) {
+                        (Some(ni), Some(nj)) => (ni, nj),
+                        _ => continue,
+                    };
+                    if mapa[ni][nj].is_empty() || ids[ni][nj].is_some() {
+                        continue;
+                    }
+                    ids[ni][nj] = Some(next_id);
+                    queue.push_back((ni, nj));
+                }
+            }
+            next_id += 1;
+        }
+    }
+    ids
+}
+
+/// Contingency table between predicted clusters and true labels.
+struct Contingency {
+    total: usize,
+    cells: BTreeMap<(usize, u8), usize>,
+    clusters: BTreeMap<usize, usize>,
+    labels: BTreeMap<u8, usize>,
+}
+
+impl Contingency {
+    fn new(pairs: &[(usize, u8)]) -> Self {
+        let mut cells = BTreeMap::new();
+        let mut clusters = BTreeMap::new();
+        let mut labels = BTreeMap::new();
+        for &(cluster, label) in pairs {
+            *cells.entry((cluster, label)).or_insert(0) += 1;
+            *clusters.entry(cluster).or_insert(0) += 1;
+            *labels.entry(label).or_insert(0) += 1;
+        }
+        Contingency {
+            total: pairs.len(),
+            cells,
+            clusters,
+            labels,
+        }
+    }
+}
+
+fn pairs_of(count: usize) -> f64 {
+    let count = count as f64;
+    count * (count - 1.0) / 2.0
+}
+
+fn entropy<'a>(counts: impl Iterator<Item = &'a usize>, total: f64) -> f64 {
+    counts
+        .map(|&count| count as f64 / total)
+        .filter(|&p| p > 0.0)
+        .map(|p| -p * p.ln())
+        .sum()
+}
+
+/// Fraction of items that belong to the majority label of their cluster.
+pub fn purity(pairs: &[(usize, u8)]) -> f64 {
+    let table = Contingency::new(pairs);
+    if table.total == 0 {
+        return 0.0;
+    }
+    let majority: usize = table
+        .clusters
+        .keys()
+        .map(|cluster| {
+            table
+                .cells
+                .range((*cluster, u8::MIN)..=(*cluster, u8::MAX))
+                .map(|(_, count)| *count)
+                .max()
+                .unwrap_or(0)
+        })
+        .sum();
+    majority as f64 / table.total as f64
+}
+
+/// Returns the Rand index and the adjusted Rand index.
+fn rand_indices(pairs: &[(usize, u8)]) -> (f64, f64) {
+    let table = Contingency::new(pairs);
+    let total_pairs = pairs_of(table.total);
+    if total_pairs == 0.0 {
+        return (1.0, 1.0);
+    }
+    let same_both: f64 = table.cells.values().map(|&count| pairs_of(count)).sum();
+    let same_cluster: f64 = table.clusters.values().map(|&count| pairs_of(count)).sum();
+    let same_label: f64 = table.labels.values().map(|&count| pairs_of(count)).sum();
+    let rand = (total_pairs + 2.0 * same_both - same_cluster - same_label) / total_pairs;
+    let expected = same_cluster * same_label / total_pairs;
+    let maximum = (same_cluster + same_label) / 2.0;
+    let adjusted = if maximum == expected {
+        1.0
+    } else {
+        (same_both - expected) / (maximum - expected)
+    };
+    (rand, adjusted)
+}
+
+pub fn rand_index(pairs: &[(usize, u8)]) -> f64 {
+    rand_indices(pairs).0
+}
+
+pub fn adjusted_rand_index(pairs: &[(usize, u8)]) -> f64 {
+    rand_indices(pairs).1
+}
+
+/// Clustering F-measure: the best F1 score of every label over all clusters,
+/// weighted by the label size.
+pub fn f_measure(pairs: &[(usize, u8)]) -> f64 {
+    let table = Contingency::new(pairs);
+    if table.total == 0 {
+        return 0.0;
+    }
+    let mut score = 0.0;
+    for (&label, &label_size) in table.labels.iter() {
+        let best = table
+            .clusters
+            .iter()
+            .map(|(&cluster, &cluster_size)| {
+                let shared = *table.cells.get(&(cluster, label)).unwrap_or(&0) as f64;
+                if shared == 0.0 {
+                    return 0.0;
+                }
+                let precision = shared / cluster_size as f64;
+                let recall = shared / label_size as f64;
+                2.0 * precision * recall / (precision + recall)
+            })
+            .fold(0.0, f64::max);
+        score += label_size as f64 / table.total as f64 * best;
+    }
+    score
+}
+
+/// Normalised mutual information, `2 I(C; L) / (H(C) + H(L))`.
+pub fn nmi(pairs: &[(usize, u8)]) -> f64 {
+    let table = Contingency::new(pairs);
+    if table.total == 0 {
+        return 0.0;
+    }
+    let total = table.total as f64;
+    let h_clusters = entropy(table.clusters.values(), total);
+    let h_labels = entropy(table.labels.values(), total);
+    if h_clusters + h_labels == 0.0 {
+        return 1.0;
+    }
+    let mutual: f64 = table
+        .cells
+        .iter()
+        .map(|(&(cluster, label), &count)| {
+            let p = count as f64 / total;
+            let p_cluster = table.clusters[&cluster] as f64 / total;
+            let p_label = table.labels[&label] as f64 / total;
+            p * (p / (p_cluster * p_label)).ln()
+        })
+        .sum();
+    2.0 * mutual / (h_clusters + h_labels)
+}
+
+/// Entropy of the item distribution over `block_size` x `block_size` blocks
+/// of the map. Lower values mean the items are more spatially concentrated.
+pub fn spatial_entropy(mapa: &MapaDef, block_size: usize) -> f64 {
+    let block_size = block_size.max(1);
+    let mut blocks: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    let mut total = 0;
+    for (i, row) in mapa.iter().enumerate() {
+        for (j, cel) in row.iter().enumerate() {
+            if !cel.is_empty() {
+                *blocks.entry((i / block_size, j / block_size)).or_insert(0) += 1;
+                total += 1;
+            }
+        }
+    }
+    if total == 0 {
+        return 0.0;
+    }
+    entropy(blocks.values(), total as f64)
+}
+
+/// Pairs the cluster id of every labelled item on the map with its label.
+pub fn cluster_label_pairs(mapa: &MapaDef, grid: &ClusterGrid) -> Vec<(usize, u8)> {
+    let mut pairs = vec![];
+    for (row, ids) in mapa.iter().zip(grid.iter()) {
+        for (cel, id) in row.iter().zip(ids.iter()) {
+            if let (Some(id), Some(label)) = (id, cel.label) {
+                pairs.push((*id, label));
+            }
+        }
+    }
+    pairs
+}
+
+pub fn evaluate(
+    mapa: &MapaDef,
+    radius: usize,
+    block_size: usize,
+    topology: Topology,
+    grid: Grid,
+) -> ClusteringMetrics {
+    let grid = spatial_clusters(mapa, radius, topology, grid);
+    let clusters = grid
+        .iter()
+        .flatten()
+        .filter_map(|id| *id)
+        .max()
+        .map_or(0, |id| id + 1);
+    let pairs = cluster_label_pairs(mapa, &grid);
+    let (rand_index, adjusted_rand_index) = rand_indices(&pairs);
+    ClusteringMetrics {
+        clusters,
+        purity: purity(&pairs),
+        rand_index,
+        adjusted_rand_index,
+        f_measure: f_measure(&pairs),
+        nmi: nmi(&pairs),
+        spatial_entropy: spatial_entropy(mapa, block_size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_retrieve::Sample;
+
+    fn item(label: u8) -> Sample {
+        Sample {
+            id: 0,
+            features: vec![label as f64],
+            label: Some(label),
+        }
+    }
+
+    #[test]
+    fn clusters_wrap_around() {
+        let mut mapa = vec![vec![Sample::default(); 6]; 6];
+        mapa[0][0] = item(1);
+        mapa[5][5] = item(1);
+        mapa[3][3] = item(2);
+        let grid = spatial_clusters(&mapa, 1, Topology::Torus, Grid::Square);
+        assert_eq!(grid[0][0], grid[5][5]);
+        assert_ne!(grid[0][0], grid[3][3]);
+        let bounded = spatial_clusters(&mapa, 1, Topology::Bounded, Grid::Square);
+        assert_ne!(bounded[0][0], bounded[5][5]);
+        let hex = spatial_clusters(&mapa, 1, Topology::Torus, Grid::Hex);
+        assert_eq!(hex[0][0], hex[5][5]);
+        let mut diagonal = vec![vec![Sample::default(); 6]; 6];
+        diagonal[2][2] = item(1);
+        diagonal[3][3] = item(1);
+        let square = spatial_clusters(&diagonal, 1, Topology::Torus, Grid::Square);
+        assert_eq!(square[2][2], square[3][3]);
+        let hex = spatial_clusters(&diagonal, 1, Topology::Torus, Grid::Hex);
+        assert_ne!(hex[2][2], hex[3][3]);
+        let metrics = evaluate(&mapa, 1, 3, Topology::Torus, Grid::Square);
+        assert_eq!(metrics.clusters, 2);
+        assert_eq!(metrics.purity, 1.0);
+        assert_eq!(metrics.adjusted_rand_index, 1.0);
+        assert!((metrics.nmi - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn known_scores() {
+        let pairs = [(0, 1), (0, 1), (0, 2), (1, 2), (1, 2), (1, 3)];
+        assert!((purity(&pairs) - 4.0 / 6.0).abs() < 1e-12);
+        assert!((rand_index(&pairs) - 9.0 / 15.0).abs() < 1e-12);
+        // sum_ij C(n_ij, 2) = 2, sum_i C(a_i, 2) = 6, sum_j C(b_j, 2) = 4
+        let expected = 6.0 * 4.0 / 15.0;
+        let ari = (2.0 - expected) / (5.0 - expected);
+        assert!((adjusted_rand_index(&pairs) - ari).abs() < 1e-12);
+        assert_eq!(f_measure(&[(0, 1), (1, 2)]), 1.0);
+    }
+}
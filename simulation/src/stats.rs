@@ -0,0 +1,195 @@
+use std::io::{self, Write};
+
+use agent::{local_density, vision_window, AgentStates, Point};
+use object::Object;
+
+use crate::{metrics::QualityMetric, Sim};
+
+/// State of the simulation after `iteration` updates.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatsRow {
+    pub iteration: usize,
+    pub carrying: usize,
+    pub searching: usize,
+    pub finishing: usize,
+    pub done: usize,
+    pub on_ground: usize,
+    pub carried: usize,
+    pub clusters: usize,
+    /// Mean local density of the items on the ground, measured with the
+    /// agents' vision radius.
+    pub mean_density: f64,
+    pub metric: f64,
+}
+
+/// Samples the state of a simulation every `every` updates.
+pub struct StatsCollector {
+    every: usize,
+    metric: QualityMetric,
+    rows: Vec<StatsRow>,
+}
+
+fn mean_density(sim: &Sim) -> f64 {
+    let radius = sim.config.agent_vision_radius;
+    let mut total = 0.0;
+    let mut count = 0;
+    for (i, row) in sim.mapa.iter().enumerate() {
+        for (j, cel) in row.iter().enumerate() {
+            if cel.is_empty() {
+                continue;
+            }
+            let vision = vision_window(
+                &sim.mapa,
+                Point { i, j },
+                radius,
+                sim.config.topology,
+                sim.config.grid,
+            );
+            total += local_density(cel, &vision, sim.alpha(), &sim.config.distance_metric);
+            count += 1;
+        }
+    }
+    if count == 0 {
+        return 0.0;
+    }
+    total / count as f64
+}
+
+impl StatsCollector {
+    pub fn new(every: usize, metric: QualityMetric) -> Self {
+        StatsCollector {
+            every: every.max(1),
+            metric,
+            rows: vec![],
+        }
+    }
+
+    /// Measures the current state of `sim`.
+    pub fn sample(sim: &Sim, metric: QualityMetric) -> StatsRow {
+        let count = |state| {
+            sim.agents
+                .iter()
+                .filter(|agent| agent.get_state() == state)
+                .count()
+        };
+        let carried = sim
+            .agents
+            .iter()
+            .filter(|agent| !agent.backpack.is_empty())
+            .count();
+        let metrics = sim.metrics();
+        StatsRow {
+            iteration: sim.get_iter() + sim.extra_iters,
+            carrying: count(AgentStates::CARRYING),
+            searching: count(AgentStates::SEARCHING),
+            finishing: count(AgentStates::FINISHING),
+            done: count(AgentStates::DONE),
+            on_ground: sim
+                .mapa
+                .iter()
+                .flatten()
+                .filter(|cel| !cel.is_empty())
+                .count(),
+            carried,
+            clusters: metrics.clusters,
+            mean_density: mean_density(sim),
+            metric: metric.value(&metrics),
+        }
+    }
+
+    /// Samples `sim` when the number of updates so far is a multiple of
+    /// `every` and was not sampled already. Returns whether a row was added.
+    pub fn record(&mut self, sim: &Sim) -> bool {
+        let iteration = sim.get_iter() + sim.extra_iters;
+        let sampled = self.rows.last().map(|row| row.iteration) == Some(iteration);
+        if !iteration.is_multiple_of(self.every) || sampled {
+            return false;
+        }
+        self.rows.push(StatsCollector::sample(sim, self.metric));
+        true
+    }
+
+    /// Samples `sim` regardless of the iteration, unless it was sampled
+    /// already at this update.
+    pub fn record_now(&mut self, sim: &Sim) {
+        let iteration = sim.get_iter() + sim.extra_iters;
+        if self.rows.last().map(|row| row.iteration) != Some(iteration) {
+            self.rows.push(StatsCollector::sample(sim, self.metric));
+        }
+    }
+
+    pub fn rows(&self) -> &[StatsRow] {
+        &self.rows
+    }
+
+    /// Writes the rows as CSV, naming the metric column after the chosen
+    /// metric.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "iteration,carrying,searching,finishing,done,on_ground,carried,clusters,mean_density,{}",
+            self.metric.name()
+        )?;
+        for row in self.rows.iter() {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{}",
+                row.iteration,
+                row.carrying,
+                row.searching,
+                row.finishing,
+                row.done,
+                row.on_ground,
+                row.carried,
+                row.clusters,
+                row.mean_density,
+                row.metric
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::config, SimState};
+
+    #[test]
+    fn samples_every_n_updates() {
+        let mut sim = Sim::create(config(9));
+        let mut stats = StatsCollector::new(500, QualityMetric::Nmi);
+        while sim.get_state() != SimState::DONE {
+            stats.record(&sim);
+            sim.update();
+        }
+        stats.record_now(&sim);
+        stats.record_now(&sim);
+        let rows = stats.rows();
+        assert_eq!(
+            rows[..4]
+                .iter()
+                .map(|row| row.iteration)
+                .collect::<Vec<_>>(),
+            [0, 500, 1000, 1500]
+        );
+        assert_eq!(rows[0].carrying, 0);
+        assert_eq!(rows[0].searching, 10);
+        for row in rows {
+            assert_eq!(row.carrying + row.searching + row.finishing + row.done, 10);
+            assert_eq!(row.on_ground + row.carried, 400);
+            assert!((0.0..=1.0).contains(&row.mean_density));
+        }
+        let last = rows.last().unwrap();
+        assert_eq!((last.done, last.carried), (10, 0));
+        assert_eq!(last.metric, sim.metrics().nmi);
+        assert!(last.mean_density > rows[0].mean_density);
+
+        let mut csv = vec![];
+        stats.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.lines().next().unwrap().ends_with(",mean_density,nmi"));
+        assert_eq!(csv.lines().count(), rows.len() + 1);
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{metrics::ClusteringMetrics, ConfigError, Sim, SimConfig, SimState};
+
+/// Values swept over by a batch run. Every empty list keeps the value of the
+/// base config, so the default grid holds the base config alone.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SweepGrid {
+    pub max_iters: Vec<usize>,
+    pub qtd_agents: Vec<usize>,
+    pub agent_vision_radius: Vec<usize>,
+    pub k1: Vec<f64>,
+    pub k2: Vec<f64>,
+}
+
+/// Outcome of a single run of a sweep.
+#[derive(Clone)]
+pub struct SweepRun {
+    /// Index of the config in the expanded grid.
+    pub cell: usize,
+    pub repetition: usize,
+    pub config: SimConfig,
+    /// Alpha the run used, estimated from the data with `estimate_alpha`.
+    pub alpha: f64,
+    pub metrics: ClusteringMetrics,
+    pub extra_iters: usize,
+    pub wall_time: Duration,
+}
+
+fn values<T: Copy>(values: &[T], base: T) -> Vec<T> {
+    if values.is_empty() {
+        vec![base]
+    } else {
+        values.to_vec()
+    }
+}
+
+impl SweepGrid {
+    /// Cartesian product of the grid values applied on top of `base`.
+    pub fn configs(&self, base: &SimConfig) -> Vec<SimConfig> {
+        let mut configs = vec![];
+        for max_iters in values(&self.max_iters, base.max_iters) {
+            for qtd_agents in values(&self.qtd_agents, base.qtd_agents) {
+                for radius in values(&self.agent_vision_radius, base.agent_vision_radius) {
+                    for k1 in values(&self.k1, base.k1) {
+                        for k2 in values(&self.k2, base.k2) {
+                            configs.push(SimConfig {
+                                max_iters,
+                                qtd_agents,
+                                agent_vision_radius: radius,
+                                k1,
+                                k2,
+                                ..base.clone()
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        configs
+    }
+}
+
+fn run(cell: usize, repetition: usize, config: SimConfig) -> SweepRun {
+    let start = Instant::now();
+    let mut sim = Sim::create(config.clone());
+    while sim.get_state() != SimState::DONE {
+        sim.update();
+    }
+    SweepRun {
+        cell,
+        repetition,
+        config,
+        alpha: sim.alpha(),
+        metrics: sim.metrics(),
+        extra_iters: sim.extra_iters,
+        wall_time: start.elapsed(),
+    }
+}
+
+/// Runs every config `repetitions` times on up to `threads` worker threads
+/// and returns the runs ordered by cell and repetition. Run number `n` is
+/// seeded with `base_seed + n`, so the results do not depend on `threads`.
+/// `on_done` is called from the worker threads as each run finishes.
+/// Every config is validated before the first run starts.
+pub fn run_sweep<F>(
+    configs: &[SimConfig],
+    repetitions: usize,
+    base_seed: u64,
+    threads: usize,
+    on_done: F,
+) -> Result<Vec<SweepRun>, ConfigError>
+where
+    F: Fn(&SweepRun) + Sync,
+{
+    for config in configs {
+        config.validate()?;
+    }
+    let total = configs.len() * repetitions;
+    let next = AtomicUsize::new(0);
+    let runs = Mutex::new(Vec::with_capacity(total));
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, total.max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= total {
+                    break;
+                }
+                let (cell, repetition) = (index / repetitions, index % repetitions);
+                let config = SimConfig {
+                    seed: Some(base_seed.wrapping_add(index as u64)),
+                    ..configs[cell].clone()
+                };
+                let result = run(cell, repetition, config);
+                on_done(&result);
+                runs.lock().unwrap().push(result);
+            });
+        }
+    });
+    let mut runs = runs.into_inner().unwrap();
+    runs.sort_by_key(|run| (run.cell, run.repetition));
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::config;
+
+    #[test]
+    fn sweeps_are_reproducible() {
+        let base = SimConfig {
+            max_iters: 500,
+            ..config(0)
+        };
+        let grid = SweepGrid {
+            agent_vision_radius: vec![1, 2],
+            k1: vec![0.1, 0.3],
+            ..SweepGrid::default()
+        };
+        let configs = grid.configs(&base);
+        assert_eq!(configs.len(), 4);
+        assert_eq!(SweepGrid::default().configs(&base).len(), 1);
+
+        let serial = run_sweep(&configs[..2], 2, 5, 1, |_| {}).unwrap();
+        let parallel = run_sweep(&configs[..2], 2, 5, 3, |_| {}).unwrap();
+        assert_eq!(serial.len(), 4);
+        for (a, b) in serial.iter().zip(parallel.iter()) {
+            assert_eq!((a.cell, a.repetition), (b.cell, b.repetition));
+            assert_eq!(a.config.seed, b.config.seed);
+            assert_eq!(a.metrics, b.metrics);
+            assert_eq!(a.extra_iters, b.extra_iters);
+        }
+        assert_eq!(serial[3].config.seed, Some(8));
+        assert_eq!(serial[3].config.k1, 0.3);
+
+        let configs = SweepGrid {
+            qtd_agents: vec![10, 0],
+            ..SweepGrid::default()
+        }
+        .configs(&base);
+        assert!(matches!(
+            run_sweep(&configs, 1, 5, 2, |_| panic!("ran an invalid sweep")),
+            Err(ConfigError::NoAgents)
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+use agent::{Agent, Grid, Point, Topology};
+use data_retrieve::Sample;
+use object::Object;
+
+use crate::events::EventKind;
+
+/// Where an input item currently is.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ItemLocation {
+    Ground(Point),
+    Carried { agent: usize },
+}
+
+/// Movement statistics of a single input item. `distance` is the sum of the
+/// distances between the centres of every pick-up cell and the following
+/// drop cell.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemStats {
+    pub pick_ups: usize,
+    pub drops: usize,
+    pub distance: f64,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct ItemTracker {
+    stats: Vec<ItemStats>,
+    picked_at: Vec<Option<Point>>,
+    height: usize,
+    width: usize,
+    topology: Topology,
+    grid: Grid,
+}
+
+impl ItemTracker {
+    pub(crate) fn new(
+        qtd_items: usize,
+        height: usize,
+        width: usize,
+        topology: Topology,
+        grid: Grid,
+    ) -> Self {
+        ItemTracker {
+            stats: vec![ItemStats::default(); qtd_items],
+            picked_at: vec![None; qtd_items],
+            height,
+            width,
+            topology,
+            grid,
+        }
+    }
+
+    /// Records the move of item `id`, see [`crate::events::item_move`], at `pos`.
+    pub(crate) fn record(&mut self, kind: EventKind, id: usize, pos: Point) {
+        match kind {
+            EventKind::Pick => {
+                self.stats[id].pick_ups += 1;
+                self.picked_at[id] = Some(pos);
+            }
+            EventKind::Drop | EventKind::Finish => {
+                self.stats[id].drops += 1;
+                if let Some(from) = self.picked_at[id].take() {
+                    let di = self.topology.offset(from.i, pos.i, self.height);
+                    let dj = self.topology.offset(from.j, pos.j, self.width);
+                    self.stats[id].distance += self.grid.center_distance(from.i, di, dj);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn stats(&self) -> &[ItemStats] {
+        &self.stats
+    }
+}
+
+pub(crate) fn backpack_id(agent: &Agent<Sample>) -> Option<usize> {
+    if agent.backpack.is_empty() {
+        return None;
+    }
+    Some(agent.backpack.id)
+}
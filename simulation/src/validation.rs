@@ -0,0 +1,265 @@
+use std::fmt::Display;
+
+use agent::{AgentConfig, Grid, Topology};
+use data_retrieve::{estimate_alpha, normalize, DataError, Sample};
+
+use crate::SimConfig;
+
+/// Smallest map side agents can move on: with fewer than 3 cells per side
+/// some moves land on the same cell and the agents can run out of moves.
+pub const MIN_MAP_SIDE: usize = 3;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    NoAgents,
+    NoIterations,
+    NoVision,
+    MapTooSmall {
+        height: usize,
+        width: usize,
+    },
+    /// The vision window is wider than the map, so it would see some cells
+    /// twice.
+    VisionTooLarge {
+        radius: usize,
+        height: usize,
+        width: usize,
+    },
+    NotEnoughCells {
+        cells: usize,
+        items: usize,
+    },
+    /// Hexagonal rows alternate their shift, so a hexagonal torus needs an
+    /// even number of rows to wrap around.
+    OddHexTorus {
+        height: usize,
+    },
+    Data(DataError),
+    Agent(agent::ConfigError),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::NoAgents => write!(f, "at least one agent is needed"),
+            ConfigError::NoIterations => write!(f, "max iters must be at least 1"),
+            ConfigError::NoVision => write!(f, "vision radius must be at least 1"),
+            ConfigError::MapTooSmall { height, width } => write!(
+                f,
+                "map is {}x{} but must be at least {}x{}",
+                height, width, MIN_MAP_SIDE, MIN_MAP_SIDE
+            ),
+            ConfigError::VisionTooLarge {
+                radius,
+                height,
+                width,
+            } => write!(
+                f,
+                "vision radius {} needs a map of at least {}x{}, got {}x{}",
+                radius,
+                2 * radius + 1,
+                2 * radius + 1,
+                height,
+                width
+            ),
+            ConfigError::NotEnoughCells { cells, items } => {
+                write!(f, "map has {} cells but {} items to place", cells, items)
+            }
+            ConfigError::OddHexTorus { height } => write!(
+                f,
+                "a hexagonal torus needs an even map height, got {}",
+                height
+            ),
+            ConfigError::Data(err) => write!(f, "unable to load the data: {}", err),
+            ConfigError::Agent(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Data(err) => Some(err),
+            ConfigError::Agent(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Input of a simulation, as [`SimConfig::prepare`] builds it.
+pub(crate) struct Prepared {
+    /// Numbered and normalised items.
+    pub(crate) data: Vec<Sample>,
+    /// `alpha` of the configuration, or the one estimated from the data.
+    pub(crate) alpha: f64,
+    pub(crate) agent_config: AgentConfig,
+}
+
+impl SimConfig {
+    /// Checks that a simulation can run with this configuration, exactly as
+    /// [`crate::Sim::try_create`] does. Loads the data to count the items
+    /// and, with `estimate_alpha`, to estimate alpha.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.prepare().map(|_| ())
+    }
+
+    /// Loads, numbers and normalises the data, checks the configuration
+    /// against it and builds the agent parameters with the alpha the
+    /// simulation will use.
+    pub(crate) fn prepare(&self) -> Result<Prepared, ConfigError> {
+        let mut data = self.data.load().map_err(ConfigError::Data)?;
+        self.check(data.len())?;
+        for (id, sample) in data.iter_mut().enumerate() {
+            sample.id = id;
+        }
+        normalize(&mut data, self.normalization);
+        let alpha = match self.estimate_alpha {
+            true => estimate_alpha(&data, &self.distance_metric),
+            false => self.alpha,
+        };
+        let agent_config = self.agent_config(alpha)?;
+        Ok(Prepared {
+            data,
+            alpha,
+            agent_config,
+        })
+    }
+
+    /// Checks everything but the agent parameters, given the number of items,
+    /// without loading the data.
+    pub fn check(&self, items: usize) -> Result<(), ConfigError> {
+        let (height, width) = (self.mapa_height, self.mapa_width);
+        if self.qtd_agents == 0 {
+            return Err(ConfigError::NoAgents);
+        }
+        if self.max_iters == 0 {
+            return Err(ConfigError::NoIterations);
+        }
+        if height < MIN_MAP_SIDE || width < MIN_MAP_SIDE {
+            return Err(ConfigError::MapTooSmall { height, width });
+        }
+        if self.agent_vision_radius == 0 {
+            return Err(ConfigError::NoVision);
+        }
+        if 2 * self.agent_vision_radius + 1 > height.min(width) {
+            return Err(ConfigError::VisionTooLarge {
+                radius: self.agent_vision_radius,
+                height,
+                width,
+            });
+        }
+        if self.grid == Grid::Hex && self.topology == Topology::Torus && height % 2 == 1 {
+            return Err(ConfigError::OddHexTorus { height });
+        }
+        if height * width < items {
+            return Err(ConfigError::NotEnoughCells {
+                cells: height * width,
+                items,
+            });
+        }
+        Ok(())
+    }
+
+    /// Agent parameters of this configuration with the given `alpha`.
+    fn agent_config(&self, alpha: f64) -> Result<AgentConfig, ConfigError> {
+        AgentConfig::new(
+            self.agent_vision_radius,
+            self.mapa_height,
+            self.mapa_width,
+            self.k1,
+            self.k2,
+            alpha,
+        )
+        .and_then(|config| config.with_distance_metric(self.distance_metric.clone()))
+        .and_then(|config| config.with_decision_model(self.decision_model.clone()))
+        .map(|config| config.with_topology(self.topology).with_grid(self.grid))
+        .map_err(ConfigError::Agent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::config, Sim};
+    use agent::DecisionModelKind;
+    use map::DataSource;
+
+    #[test]
+    fn rejects_unrunnable_configs() {
+        let check = |edit: fn(&mut SimConfig)| {
+            let mut config = config(1);
+            edit(&mut config);
+            let err = Sim::try_create(config.clone()).err().unwrap();
+            assert_eq!(config.validate().unwrap_err().to_string(), err.to_string());
+            err
+        };
+        assert!(matches!(
+            check(|config| config.mapa_width = 9),
+            ConfigError::NotEnoughCells {
+                cells: 360,
+                items: 400
+            }
+        ));
+        assert!(matches!(
+            check(|config| config.agent_vision_radius = 20),
+            ConfigError::VisionTooLarge { radius: 20, .. }
+        ));
+        assert!(matches!(
+            check(|config| config.qtd_agents = 0),
+            ConfigError::NoAgents
+        ));
+        assert!(matches!(
+            check(|config| config.max_iters = 0),
+            ConfigError::NoIterations
+        ));
+        assert!(matches!(
+            check(|config| config.mapa_width = 1),
+            ConfigError::MapTooSmall {
+                height: 40,
+                width: 1
+            }
+        ));
+        assert!(matches!(
+            check(|config| config.k1 = -1.0),
+            ConfigError::Agent(agent::ConfigError::InvalidK1(_))
+        ));
+        assert!(matches!(
+            check(|config| config.decision_model = DecisionModelKind::Sigmoid { slope: 0.0 }),
+            ConfigError::Agent(agent::ConfigError::InvalidSigmoidSlope(_))
+        ));
+        assert!(matches!(
+            check(|config| {
+                config.grid = Grid::Hex;
+                config.mapa_height = 41;
+            }),
+            ConfigError::OddHexTorus { height: 41 }
+        ));
+        assert!(config(1).validate().is_ok());
+    }
+
+    #[test]
+    fn validates_the_estimated_alpha() {
+        let agree = |config: &SimConfig| {
+            let created = Sim::try_create(config.clone()).map(|_| ());
+            assert_eq!(
+                config.validate().map_err(|err| err.to_string()),
+                created.map_err(|err| err.to_string())
+            );
+            config.validate()
+        };
+        let mut config = config(1);
+        config.estimate_alpha = true;
+        config.alpha = -1.0;
+        assert!(agree(&config).is_ok());
+        let same = Sample {
+            id: 0,
+            features: vec![1.0, 2.0],
+            label: Some(1),
+        };
+        config.data = DataSource::Items(vec![same; 10]);
+        assert!(matches!(
+            agree(&config),
+            Err(ConfigError::Agent(agent::ConfigError::InvalidAlpha(alpha))) if alpha == 0.0
+        ));
+    }
+}